@@ -6,18 +6,49 @@ mod modules;
 mod types;
 
 // Local module imports
+use modules::boss::boss_definition_init;
 use modules::building_piece_variant::building_piece_variant_init;
-use modules::inventory::item_init;
+use modules::crafting::crafting_init;
+use modules::dropped_item::dropped_item_init;
+use modules::duel::duel_init;
+use modules::faction::faction_init;
+use modules::impulse::impulse_init;
+use modules::item_definition::{item_definition_init, item_definition_validate};
 use modules::lootable::lootable_item_type_init;
+use modules::npc::npc_definition_init;
 use modules::player::{player, player_set_online_status};
+use modules::player_stats::player_stats_init;
+use modules::projectile::projectile_type_init;
+use modules::pvp::pvp_init;
+use modules::stamina::stamina_init;
+use modules::status_effect::status_effect_init;
+use modules::threat::threat_init;
+use modules::trade::trade_init;
 use modules::world_spawn::world_spawn_init;
+use modules::zone::zone_init;
 
 #[spacetimedb::reducer(init)]
 pub fn init(ctx: &ReducerContext) -> Result<(), String> {
     world_spawn_init(ctx)?;
     building_piece_variant_init(ctx)?;
-    item_init(ctx)?;
+    item_definition_init(ctx)?;
     lootable_item_type_init(ctx)?;
+    status_effect_init(ctx)?;
+    projectile_type_init(ctx)?;
+    stamina_init(ctx)?;
+    npc_definition_init(ctx)?;
+    boss_definition_init(ctx)?;
+    threat_init(ctx)?;
+    faction_init(ctx)?;
+    pvp_init(ctx)?;
+    zone_init(ctx)?;
+    impulse_init(ctx)?;
+    duel_init(ctx)?;
+    dropped_item_init(ctx)?;
+    trade_init(ctx)?;
+    crafting_init(ctx)?;
+    player_stats_init(ctx)?;
+    item_definition_validate(ctx)?;
     Ok(())
 }
 
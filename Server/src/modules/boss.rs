@@ -0,0 +1,627 @@
+use crate::modules::admin::require_admin;
+use crate::modules::combat::{is_staggered, DbDamageType};
+use crate::modules::entity::{
+    entity, entity_apply_damage_internal, entity_find_in_radius, DbEntityKind,
+};
+use crate::modules::inventory::inventory_add_item_partial;
+use crate::modules::item_definition::item_definition_check_all;
+use crate::modules::line_of_sight::has_line_of_sight;
+use crate::modules::npc::{npc_definition, npc_roll_loot, npc_spawn, DbLootEntry};
+use crate::modules::player::player;
+use crate::modules::player_stats::player_stats_on_loot;
+use crate::modules::status_effect::status_effect_apply_internal;
+use crate::types::DbVector3;
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// An attack a boss casts on a timer, hitting every player around it
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbBossAbility {
+    pub name: String,
+    /// Time in microseconds between casts
+    pub interval_us: i64,
+    pub damage: f32,
+    pub damage_type: DbDamageType,
+    /// Players within this distance of the boss are hit
+    pub radius: f32,
+    /// References StatusEffectDefinition.effect_id, applied to everyone hit
+    pub status_effect_id: Option<u32>,
+}
+
+/// A stage of a boss fight
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbBossPhase {
+    /// The phase starts once the boss's health fraction drops to this value (1.0 = from the start)
+    pub health_threshold: f32,
+    pub abilities: Vec<DbBossAbility>,
+}
+
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbEncounterStatus {
+    /// Boss is alive and nobody has engaged it yet
+    Waiting,
+    /// Boss is fighting
+    Active,
+    /// Boss was killed and loot was handed out
+    Defeated,
+}
+
+/// Scripted fight layered on top of an NPC definition
+#[spacetimedb::table(name = boss_definition, public)]
+pub struct BossDefinition {
+    #[primary_key]
+    pub boss_def_id: u32,
+    /// References NpcDefinition.npc_def_id used for the boss's stats and model
+    pub npc_def_id: u32,
+    /// Phases ordered by descending health threshold
+    pub phases: Vec<DbBossPhase>,
+    /// Rolled separately for every eligible participant
+    pub loot_table: Vec<DbLootEntry>,
+    /// Share of the total contribution a player needs to receive loot (0.0 - 1.0)
+    pub min_contribution_share: f32,
+}
+
+/// One fight against a spawned boss
+#[spacetimedb::table(name = boss_encounter, public)]
+pub struct BossEncounter {
+    #[primary_key]
+    #[auto_inc]
+    pub encounter_id: u32,
+    /// References BossDefinition.boss_def_id
+    pub boss_def_id: u32,
+    /// References Entity.entity_id of the boss NPC
+    #[unique]
+    pub boss_entity_id: u32,
+    pub status: DbEncounterStatus,
+    /// Index into BossDefinition.phases
+    pub phase_index: u32,
+    /// Timestamp when the boss was first engaged (0 = not engaged)
+    pub started_at_us: i64,
+    /// Timestamp when the boss was defeated (0 = not defeated)
+    pub ended_at_us: i64,
+}
+
+/// A player's part in an encounter
+#[spacetimedb::table(name = boss_participant, public)]
+pub struct BossParticipant {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    /// References BossEncounter.encounter_id
+    #[index(btree)]
+    pub encounter_id: u32,
+    pub identity: Identity,
+    /// References Entity.entity_id of the player
+    pub entity_id: u32,
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+}
+
+/// Timer casting one boss ability (one row per ability of the current phase)
+#[spacetimedb::table(name = boss_ability_schedule, scheduled(boss_ability_cast))]
+pub struct BossAbilitySchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+    /// References BossEncounter.encounter_id
+    #[index(btree)]
+    pub encounter_id: u32,
+    /// Index into the current phase's abilities
+    pub ability_index: u32,
+}
+
+impl BossParticipant {
+    fn contribution(&self) -> f32 {
+        self.damage_dealt + self.damage_taken
+    }
+}
+
+/// Initialize the default boss definitions
+pub fn boss_definition_init(ctx: &ReducerContext) -> Result<(), String> {
+    let cleave = DbBossAbility {
+        name: "Cleave".to_string(),
+        interval_us: 8_000_000, // 8 seconds
+        damage: 20.0,
+        damage_type: DbDamageType::Slash,
+        radius: 4.0,
+        status_effect_id: None,
+    };
+
+    // Bandit Chief - boss_def_id 0
+    ctx.db.boss_definition().insert(BossDefinition {
+        boss_def_id: 0,
+        npc_def_id: 3, // Bandit Chief
+        phases: vec![
+            DbBossPhase {
+                health_threshold: 1.0,
+                abilities: vec![cleave.clone()],
+            },
+            DbBossPhase {
+                health_threshold: 0.6,
+                abilities: vec![
+                    DbBossAbility {
+                        interval_us: 6_000_000, // 6 seconds
+                        ..cleave.clone()
+                    },
+                    DbBossAbility {
+                        name: "War Cry".to_string(),
+                        interval_us: 15_000_000, // 15 seconds
+                        damage: 0.0,
+                        damage_type: DbDamageType::Blunt,
+                        radius: 12.0,
+                        status_effect_id: Some(4), // Vulnerable
+                    },
+                ],
+            },
+            DbBossPhase {
+                health_threshold: 0.25,
+                abilities: vec![DbBossAbility {
+                    name: "Whirlwind".to_string(),
+                    interval_us: 4_000_000, // 4 seconds
+                    damage: 15.0,
+                    damage_type: DbDamageType::Slash,
+                    radius: 5.0,
+                    status_effect_id: Some(1), // Bleeding
+                }],
+            },
+        ],
+        loot_table: vec![
+            DbLootEntry {
+                item_id: 3, // Arrow
+                min_quantity: 10,
+                max_quantity: 20,
+                chance: 1.0,
+            },
+            DbLootEntry {
+                item_id: 4, // Hide
+                min_quantity: 2,
+                max_quantity: 5,
+                chance: 0.5,
+            },
+        ],
+        min_contribution_share: 0.05,
+    });
+
+    log::info!("Initialized default boss definitions");
+    Ok(())
+}
+
+/// Creates a new boss definition (admin only)
+#[spacetimedb::reducer]
+pub fn boss_create_definition(
+    ctx: &ReducerContext,
+    boss_def_id: u32,
+    npc_def_id: u32,
+    phases: Vec<DbBossPhase>,
+    loot_table: Vec<DbLootEntry>,
+    min_contribution_share: f32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx
+        .db
+        .boss_definition()
+        .boss_def_id()
+        .find(boss_def_id)
+        .is_some()
+    {
+        return Err(format!("Boss definition {} already exists", boss_def_id));
+    }
+    if ctx
+        .db
+        .npc_definition()
+        .npc_def_id()
+        .find(npc_def_id)
+        .is_none()
+    {
+        return Err(format!("NPC definition {} does not exist", npc_def_id));
+    }
+    if phases.is_empty() {
+        return Err("A boss needs at least one phase".to_string());
+    }
+    item_definition_check_all(ctx, loot_table.iter().map(|entry| entry.item_id))?;
+    if phases
+        .windows(2)
+        .any(|pair| pair[1].health_threshold >= pair[0].health_threshold)
+    {
+        return Err("Phases must be ordered by descending health threshold".to_string());
+    }
+    if phases
+        .iter()
+        .flat_map(|phase| &phase.abilities)
+        .any(|ability| ability.interval_us <= 0)
+    {
+        return Err("Ability intervals must be positive".to_string());
+    }
+
+    ctx.db.boss_definition().insert(BossDefinition {
+        boss_def_id,
+        npc_def_id,
+        phases,
+        loot_table,
+        min_contribution_share,
+    });
+    log::info!("Created boss definition with boss_def_id: {}", boss_def_id);
+    Ok(())
+}
+
+/// Spawn a boss and open its encounter (admin only)
+#[spacetimedb::reducer]
+pub fn boss_spawn(
+    ctx: &ReducerContext,
+    boss_def_id: u32,
+    position: DbVector3,
+    rotation: DbVector3,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let definition = ctx
+        .db
+        .boss_definition()
+        .boss_def_id()
+        .find(boss_def_id)
+        .ok_or("Boss definition not found")?;
+
+    let npc = npc_spawn(ctx, definition.npc_def_id, position, rotation, 0)?;
+    let encounter = ctx.db.boss_encounter().insert(BossEncounter {
+        encounter_id: 0,
+        boss_def_id,
+        boss_entity_id: npc.entity_id,
+        status: DbEncounterStatus::Waiting,
+        phase_index: 0,
+        started_at_us: 0,
+        ended_at_us: 0,
+    });
+
+    log::info!(
+        "Spawned boss {} for encounter {}",
+        npc.entity_id,
+        encounter.encounter_id
+    );
+    Ok(())
+}
+
+/// Replace the ability timers of an encounter with those of a phase
+fn boss_schedule_phase(ctx: &ReducerContext, encounter_id: u32, phase: Option<&DbBossPhase>) {
+    let scheduled_ids: Vec<_> = ctx
+        .db
+        .boss_ability_schedule()
+        .encounter_id()
+        .filter(encounter_id)
+        .map(|schedule| schedule.scheduled_id)
+        .collect();
+    for scheduled_id in scheduled_ids {
+        ctx.db
+            .boss_ability_schedule()
+            .scheduled_id()
+            .delete(scheduled_id);
+    }
+
+    for (ability_index, ability) in phase
+        .into_iter()
+        .flat_map(|phase| phase.abilities.iter())
+        .enumerate()
+    {
+        ctx.db.boss_ability_schedule().insert(BossAbilitySchedule {
+            scheduled_id: 0,
+            scheduled_at: TimeDuration::from_micros(ability.interval_us).into(),
+            encounter_id,
+            ability_index: ability_index as u32,
+        });
+    }
+}
+
+fn boss_record_participant(
+    ctx: &ReducerContext,
+    encounter_id: u32,
+    entity_id: u32,
+    damage_dealt: f32,
+    damage_taken: f32,
+) {
+    let player = match ctx.db.player().entity_id().find(entity_id) {
+        Some(player) => player,
+        None => return,
+    };
+
+    let existing = ctx
+        .db
+        .boss_participant()
+        .encounter_id()
+        .filter(encounter_id)
+        .find(|participant| participant.identity == player.identity);
+
+    match existing {
+        Some(mut participant) => {
+            participant.damage_dealt += damage_dealt;
+            participant.damage_taken += damage_taken;
+            ctx.db.boss_participant().id().update(participant);
+        }
+        None => {
+            ctx.db.boss_participant().insert(BossParticipant {
+                id: 0,
+                encounter_id,
+                identity: player.identity,
+                entity_id,
+                damage_dealt,
+                damage_taken,
+            });
+        }
+    }
+}
+
+/// Track contribution and phase changes for damage dealt to or by a boss
+pub fn boss_on_damage(
+    ctx: &ReducerContext,
+    attacker_entity_id: u32,
+    target_entity_id: u32,
+    damage: f32,
+) {
+    // A player was hit by a boss
+    if let Some(encounter) = ctx
+        .db
+        .boss_encounter()
+        .boss_entity_id()
+        .find(attacker_entity_id)
+        .filter(|encounter| encounter.status == DbEncounterStatus::Active)
+    {
+        boss_record_participant(ctx, encounter.encounter_id, target_entity_id, 0.0, damage);
+        return;
+    }
+
+    // A boss was hit
+    let mut encounter = match ctx
+        .db
+        .boss_encounter()
+        .boss_entity_id()
+        .find(target_entity_id)
+        .filter(|encounter| encounter.status != DbEncounterStatus::Defeated)
+    {
+        Some(encounter) => encounter,
+        None => return,
+    };
+    let definition = match ctx
+        .db
+        .boss_definition()
+        .boss_def_id()
+        .find(encounter.boss_def_id)
+    {
+        Some(definition) => definition,
+        None => return,
+    };
+    let boss = match ctx.db.entity().entity_id().find(target_entity_id) {
+        Some(boss) => boss,
+        None => return,
+    };
+
+    if encounter.status == DbEncounterStatus::Waiting {
+        encounter.status = DbEncounterStatus::Active;
+        encounter.started_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+        encounter.phase_index = 0;
+        boss_schedule_phase(ctx, encounter.encounter_id, definition.phases.first());
+        log::info!("Encounter {} started", encounter.encounter_id);
+    }
+
+    boss_record_participant(ctx, encounter.encounter_id, attacker_entity_id, damage, 0.0);
+
+    // Move to the deepest phase whose threshold has been reached
+    let health_fraction = boss.health / boss.max_health;
+    let phase_index = definition
+        .phases
+        .iter()
+        .rposition(|phase| health_fraction <= phase.health_threshold)
+        .unwrap_or(0) as u32;
+    if phase_index > encounter.phase_index {
+        encounter.phase_index = phase_index;
+        boss_schedule_phase(
+            ctx,
+            encounter.encounter_id,
+            definition.phases.get(phase_index as usize),
+        );
+        log::info!(
+            "Encounter {} entered phase {}",
+            encounter.encounter_id,
+            phase_index
+        );
+    }
+
+    ctx.db.boss_encounter().encounter_id().update(encounter);
+}
+
+/// Reset an encounter when its boss gives up the fight
+pub fn boss_on_leash(ctx: &ReducerContext, boss_entity_id: u32) {
+    let mut encounter = match ctx
+        .db
+        .boss_encounter()
+        .boss_entity_id()
+        .find(boss_entity_id)
+        .filter(|encounter| encounter.status == DbEncounterStatus::Active)
+    {
+        Some(encounter) => encounter,
+        None => return,
+    };
+
+    boss_schedule_phase(ctx, encounter.encounter_id, None);
+
+    let participant_ids: Vec<_> = ctx
+        .db
+        .boss_participant()
+        .encounter_id()
+        .filter(encounter.encounter_id)
+        .map(|participant| participant.id)
+        .collect();
+    for id in participant_ids {
+        ctx.db.boss_participant().id().delete(id);
+    }
+
+    log::info!("Encounter {} reset", encounter.encounter_id);
+    encounter.status = DbEncounterStatus::Waiting;
+    encounter.phase_index = 0;
+    encounter.started_at_us = 0;
+    ctx.db.boss_encounter().encounter_id().update(encounter);
+}
+
+/// Finish an encounter when its boss dies, rolling loot for every eligible participant
+pub fn boss_on_killed(ctx: &ReducerContext, boss_entity_id: u32) {
+    let mut encounter = match ctx
+        .db
+        .boss_encounter()
+        .boss_entity_id()
+        .find(boss_entity_id)
+        .filter(|encounter| encounter.status != DbEncounterStatus::Defeated)
+    {
+        Some(encounter) => encounter,
+        None => return,
+    };
+
+    boss_schedule_phase(ctx, encounter.encounter_id, None);
+
+    if let Some(definition) = ctx
+        .db
+        .boss_definition()
+        .boss_def_id()
+        .find(encounter.boss_def_id)
+    {
+        let participants: Vec<_> = ctx
+            .db
+            .boss_participant()
+            .encounter_id()
+            .filter(encounter.encounter_id)
+            .collect();
+        let total: f32 = participants.iter().map(|p| p.contribution()).sum();
+
+        for participant in participants {
+            if total <= 0.0
+                || participant.contribution() / total < definition.min_contribution_share
+            {
+                continue;
+            }
+
+            for loot in npc_roll_loot(ctx, &definition.loot_table) {
+                let identity = participant.identity;
+                match inventory_add_item_partial(ctx, identity, loot.id, loot.quantity) {
+                    Ok(overflow) => {
+                        if overflow < loot.quantity {
+                            player_stats_on_loot(ctx, identity, loot.id, loot.quantity - overflow);
+                        }
+                        if overflow > 0 {
+                            log::warn!(
+                                "Inventory of {:?} is full, {} of item {} was lost",
+                                identity,
+                                overflow,
+                                loot.id
+                            );
+                        }
+                    }
+                    Err(err) => log::warn!("Failed to give boss loot to {:?}: {}", identity, err),
+                }
+            }
+        }
+    }
+
+    encounter.status = DbEncounterStatus::Defeated;
+    encounter.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    log::info!("Encounter {} defeated", encounter.encounter_id);
+    ctx.db.boss_encounter().encounter_id().update(encounter);
+}
+
+/// Cast one of a boss's abilities on every player in its radius
+#[spacetimedb::reducer]
+pub fn boss_ability_cast(
+    ctx: &ReducerContext,
+    schedule: BossAbilitySchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `boss_ability_cast` may only be invoked by the scheduler".to_string());
+    }
+
+    let encounter = ctx
+        .db
+        .boss_encounter()
+        .encounter_id()
+        .find(schedule.encounter_id)
+        .filter(|encounter| encounter.status == DbEncounterStatus::Active);
+    let definition = encounter.as_ref().and_then(|encounter| {
+        ctx.db
+            .boss_definition()
+            .boss_def_id()
+            .find(encounter.boss_def_id)
+    });
+    let ability =
+        encounter
+            .as_ref()
+            .zip(definition.as_ref())
+            .and_then(|(encounter, definition)| {
+                definition
+                    .phases
+                    .get(encounter.phase_index as usize)?
+                    .abilities
+                    .get(schedule.ability_index as usize)
+                    .cloned()
+            });
+    let boss = encounter
+        .as_ref()
+        .and_then(|encounter| ctx.db.entity().entity_id().find(encounter.boss_entity_id));
+
+    let (ability, boss) = match (ability, boss) {
+        (Some(ability), Some(boss)) if boss.health > 0.0 => (ability, boss),
+        _ => {
+            // Encounter is over or the phase changed underneath this timer
+            ctx.db
+                .boss_ability_schedule()
+                .scheduled_id()
+                .delete(schedule.scheduled_id);
+            return Ok(());
+        }
+    };
+
+    if is_staggered(&boss, ctx.timestamp.to_micros_since_unix_epoch()) {
+        return Ok(());
+    }
+
+    log::info!("Boss {} casts {}", boss.entity_id, ability.name);
+
+    let targets = entity_find_in_radius(
+        ctx,
+        &boss.position,
+        ability.radius,
+        Some(DbEntityKind::Player),
+    );
+    for target in targets {
+        if target.health <= 0.0 || !has_line_of_sight(ctx, &boss.position, &target.position, None) {
+            continue;
+        }
+
+        if ability.damage > 0.0 {
+            if let Err(err) = entity_apply_damage_internal(
+                ctx,
+                boss.entity_id,
+                target.entity_id,
+                ability.damage,
+                ability.damage_type,
+            ) {
+                log::debug!(
+                    "{} missed entity {}: {}",
+                    ability.name,
+                    target.entity_id,
+                    err
+                );
+                continue;
+            }
+        }
+
+        if let Some(effect_id) = ability.status_effect_id {
+            if let Err(err) =
+                status_effect_apply_internal(ctx, target.entity_id, effect_id, Some(boss.entity_id))
+            {
+                log::warn!(
+                    "{} failed to apply effect {}: {}",
+                    ability.name,
+                    effect_id,
+                    err
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
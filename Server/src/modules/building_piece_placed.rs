@@ -1,8 +1,14 @@
 use crate::modules::admin::is_admin;
 use crate::modules::building_piece_variant::building_piece_variant_get;
+use crate::modules::container::{
+    container_check_empty, container_create, container_on_piece_removed,
+};
+use crate::modules::entity::{entity_create, entity_delete, DbEntityKind};
 use crate::modules::inventory::{
     inventory_add_item_internal, inventory_get_item, inventory_remove_item_internal,
 };
+use crate::modules::player_stats::player_stats_update;
+use crate::modules::zone::zone_check_build;
 use crate::types::DbVector3;
 use spacetimedb::{Identity, ReducerContext, SpacetimeType, Table};
 
@@ -12,6 +18,10 @@ pub enum DbBuildingPieceType {
     Wall,
     Floor,
     Stair,
+    /// Storage such as chests and crates (see Container)
+    Container,
+    /// Required nearby for some recipes (see Recipe)
+    CraftingStation,
 }
 
 #[spacetimedb::table(name = building_piece_placed, public)]
@@ -19,7 +29,11 @@ pub struct DbBuildingPiecePlaced {
     #[primary_key]
     #[auto_inc]
     pub piece_id: u32,
+    /// References Entity.entity_id (kind Structure) holding the piece's health
+    #[unique]
+    pub entity_id: u32,
     pub owner: Identity,
+    #[index(btree)]
     pub variant_id: u32,
     pub position: DbVector3,
     pub rotation: DbVector3,
@@ -32,6 +46,9 @@ pub fn building_piece_place(
     position: DbVector3,
     rotation: DbVector3,
 ) -> Result<(), String> {
+    // Some zones (e.g., around spawn) don't allow building
+    zone_check_build(ctx, &position)?;
+
     // Get the building piece variant to check its cost
     let variant = building_piece_variant_get(ctx, variant_id)?;
 
@@ -49,25 +66,39 @@ pub fn building_piece_place(
         inventory_remove_item_internal(ctx, ctx.sender, cost.item_id, cost.quantity)?;
     }
 
-    // Place the building piece
+    // Place the building piece, backed by a structure entity for health
+    let entity = entity_create(
+        ctx,
+        DbEntityKind::Structure,
+        position.clone(),
+        rotation.clone(),
+        variant.max_health,
+    )?;
     let piece = DbBuildingPiecePlaced {
         piece_id: 0,
+        entity_id: entity.entity_id,
         owner: ctx.sender,
         variant_id,
         position,
         rotation,
     };
-    ctx.db.building_piece_placed().insert(piece);
+    let piece = ctx.db.building_piece_placed().insert(piece);
+    if variant.container_slots > 0 {
+        container_create(ctx, piece.piece_id, variant.container_slots);
+    }
+    player_stats_update(ctx, ctx.sender, |stats| stats.pieces_built += 1);
     Ok(())
 }
 
 #[spacetimedb::reducer]
 pub fn building_piece_remove(ctx: &ReducerContext, piece_id: u32) -> Result<(), String> {
-    if let Some(piece) = ctx.db.building_piece_placed().piece_id().find(&piece_id) {
+    if let Some(piece) = ctx.db.building_piece_placed().piece_id().find(piece_id) {
         let is_owner = piece.owner == ctx.sender;
         let is_admin = is_admin(ctx, ctx.sender);
 
         if is_owner || is_admin {
+            container_check_empty(ctx, piece_id)?;
+
             // Get the building piece variant to refund materials
             let variant = building_piece_variant_get(ctx, piece.variant_id)?;
 
@@ -76,7 +107,10 @@ pub fn building_piece_remove(ctx: &ReducerContext, piece_id: u32) -> Result<(),
                 inventory_add_item_internal(ctx, piece.owner, cost.item_id, cost.quantity)?;
             }
 
-            ctx.db.building_piece_placed().piece_id().delete(&piece_id);
+            container_on_piece_removed(ctx, &piece);
+            ctx.db.building_piece_placed().piece_id().delete(piece_id);
+            entity_delete(ctx, piece.entity_id);
+            player_stats_update(ctx, ctx.sender, |stats| stats.pieces_removed += 1);
             Ok(())
         } else {
             Err("Only the owner can remove this building piece".to_string())
@@ -85,3 +119,17 @@ pub fn building_piece_remove(ctx: &ReducerContext, piece_id: u32) -> Result<(),
         Err("Building piece not found".to_string())
     }
 }
+
+/// Remove a building piece whose structure entity was destroyed (no refund)
+/// A destroyed container spills its contents
+pub fn building_piece_destroy(ctx: &ReducerContext, entity_id: u32) {
+    if let Some(piece) = ctx.db.building_piece_placed().entity_id().find(entity_id) {
+        container_on_piece_removed(ctx, &piece);
+        ctx.db
+            .building_piece_placed()
+            .piece_id()
+            .delete(piece.piece_id);
+        log::info!("Building piece {} was destroyed", piece.piece_id);
+    }
+    entity_delete(ctx, entity_id);
+}
@@ -1,4 +1,6 @@
+use crate::modules::admin::require_admin;
 use crate::modules::building_piece_placed::DbBuildingPieceType;
+use crate::types::{DbBoundingBox, DbVector3};
 use spacetimedb::{ReducerContext, SpacetimeType, Table};
 
 #[derive(SpacetimeType, Clone, Debug)]
@@ -15,6 +17,10 @@ pub struct DbBuildingPieceVariant {
     pub variant_name: String,
     pub build_cost: Vec<DbBuildingCost>,
     pub max_health: f32,
+    /// Number of storage slots in placed pieces (0 = not a container)
+    pub container_slots: u32,
+    /// Collision volume in the piece's local space (used for line-of-sight checks)
+    pub bounds: Vec<DbBoundingBox>,
 }
 
 pub fn building_piece_variant_get(
@@ -25,17 +31,52 @@ pub fn building_piece_variant_get(
         .db
         .building_piece_variant()
         .variant_id()
-        .find(&variant_id)
+        .find(variant_id)
         .ok_or("Building piece variant not found")?;
 
     Ok(variant)
 }
 
+/// Shorthand for a local-space bounding box from its center and half extents
+fn bounds(center: (f32, f32, f32), half_extents: (f32, f32, f32)) -> DbBoundingBox {
+    DbBoundingBox {
+        center: DbVector3 {
+            x: center.0,
+            y: center.1,
+            z: center.2,
+        },
+        half_extents: DbVector3 {
+            x: half_extents.0,
+            y: half_extents.1,
+            z: half_extents.2,
+        },
+    }
+}
+
+/// Replace the collision volume of a building piece variant (admin only)
+#[spacetimedb::reducer]
+pub fn building_piece_variant_set_bounds(
+    ctx: &ReducerContext,
+    variant_id: u32,
+    bounds: Vec<DbBoundingBox>,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let mut variant = building_piece_variant_get(ctx, variant_id)?;
+    variant.bounds = bounds;
+    ctx.db.building_piece_variant().variant_id().update(variant);
+
+    log::info!("Updated bounds for building piece variant {}", variant_id);
+    Ok(())
+}
+
 pub fn building_piece_variant_init(ctx: &ReducerContext) -> Result<(), String> {
     foundation_variants(ctx)?;
     floor_variants(ctx)?;
     wall_variants(ctx)?;
     stair_variants(ctx)?;
+    container_variants(ctx)?;
+    crafting_station_variants(ctx)?;
     Ok(())
 }
 
@@ -51,6 +92,8 @@ fn foundation_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.5, 0.0), (2.0, 0.5, 2.0))],
         });
     ctx.db
         .building_piece_variant()
@@ -63,6 +106,8 @@ fn foundation_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.5, 0.0), (2.0, 0.5, 1.75))],
         });
     Ok(())
 }
@@ -79,6 +124,8 @@ fn floor_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.1, 0.0), (2.0, 0.1, 2.0))],
         });
     ctx.db
         .building_piece_variant()
@@ -91,6 +138,8 @@ fn floor_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.1, 0.0), (2.0, 0.1, 1.0))],
         });
     ctx.db
         .building_piece_variant()
@@ -103,6 +152,8 @@ fn floor_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.1, 0.0), (1.0, 0.1, 1.0))],
         });
     ctx.db
         .building_piece_variant()
@@ -115,6 +166,8 @@ fn floor_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.1, 0.0), (2.0, 0.1, 1.75))],
         });
     Ok(())
 }
@@ -131,6 +184,8 @@ fn wall_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 1.5, 0.0), (2.0, 1.5, 0.1))],
         });
     ctx.db
         .building_piece_variant()
@@ -143,6 +198,8 @@ fn wall_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.75, 0.0), (2.0, 0.75, 0.1))],
         });
     ctx.db
         .building_piece_variant()
@@ -155,6 +212,8 @@ fn wall_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 1.5, 0.0), (1.0, 1.5, 0.1))],
         });
     ctx.db
         .building_piece_variant()
@@ -167,6 +226,13 @@ fn wall_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![
+                // Frame either side of the doorway, and the lintel above it
+                bounds((-1.4, 1.5, 0.0), (0.6, 1.5, 0.1)),
+                bounds((1.4, 1.5, 0.0), (0.6, 1.5, 0.1)),
+                bounds((0.0, 2.75, 0.0), (0.8, 0.25, 0.1)),
+            ],
         });
     ctx.db
         .building_piece_variant()
@@ -179,6 +245,14 @@ fn wall_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![
+                // Wall below, above and either side of the window opening
+                bounds((0.0, 0.5, 0.0), (2.0, 0.5, 0.1)),
+                bounds((0.0, 2.6, 0.0), (2.0, 0.4, 0.1)),
+                bounds((-1.4, 1.6, 0.0), (0.6, 0.6, 0.1)),
+                bounds((1.4, 1.6, 0.0), (0.6, 0.6, 0.1)),
+            ],
         });
     Ok(())
 }
@@ -195,6 +269,8 @@ fn stair_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 1.5, 0.0), (1.0, 1.5, 2.0))],
         });
     ctx.db
         .building_piece_variant()
@@ -207,6 +283,64 @@ fn stair_variants(ctx: &ReducerContext) -> Result<(), String> {
                 quantity: 5,
             }],
             max_health: 100.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.75, 0.0), (1.0, 0.75, 1.0))],
+        });
+    Ok(())
+}
+
+fn container_variants(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db
+        .building_piece_variant()
+        .insert(DbBuildingPieceVariant {
+            variant_id: 13,
+            piece_type: DbBuildingPieceType::Container,
+            variant_name: "Chest".to_string(),
+            build_cost: vec![DbBuildingCost {
+                item_id: 2,
+                quantity: 10,
+            }],
+            max_health: 150.0,
+            container_slots: 16,
+            bounds: vec![bounds((0.0, 0.4, 0.0), (0.6, 0.4, 0.4))],
+        });
+    ctx.db
+        .building_piece_variant()
+        .insert(DbBuildingPieceVariant {
+            variant_id: 14,
+            piece_type: DbBuildingPieceType::Container,
+            variant_name: "Crate".to_string(),
+            build_cost: vec![DbBuildingCost {
+                item_id: 0,
+                quantity: 10,
+            }],
+            max_health: 100.0,
+            container_slots: 8,
+            bounds: vec![bounds((0.0, 0.4, 0.0), (0.4, 0.4, 0.4))],
+        });
+    Ok(())
+}
+
+fn crafting_station_variants(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db
+        .building_piece_variant()
+        .insert(DbBuildingPieceVariant {
+            variant_id: 15,
+            piece_type: DbBuildingPieceType::CraftingStation,
+            variant_name: "Workbench".to_string(),
+            build_cost: vec![
+                DbBuildingCost {
+                    item_id: 2,
+                    quantity: 10,
+                },
+                DbBuildingCost {
+                    item_id: 1,
+                    quantity: 5,
+                },
+            ],
+            max_health: 150.0,
+            container_slots: 0,
+            bounds: vec![bounds((0.0, 0.5, 0.0), (1.0, 0.5, 0.5))],
         });
     Ok(())
 }
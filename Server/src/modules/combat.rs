@@ -0,0 +1,590 @@
+use crate::modules::entity::{entity, Entity};
+use crate::modules::player::player;
+use crate::modules::stamina::{stamina_spend, DODGE_STAMINA_COST};
+use crate::modules::status_effect::status_effect_defense_modifiers;
+use crate::types::DbVector3;
+use spacetimedb::{ReducerContext, SpacetimeType};
+
+/// Armor value at which physical damage is halved
+const ARMOR_HALVING_VALUE: f32 = 100.0;
+/// Highest resistance an entity can reach (90% reduction)
+const MAX_RESISTANCE: f32 = 0.9;
+/// Lowest resistance an entity can reach (double damage)
+const MIN_RESISTANCE: f32 = -1.0;
+/// Fraction of damage absorbed by a block
+const BLOCK_DAMAGE_REDUCTION: f32 = 0.7;
+/// Cosine of the half-angle of the arc in front of an entity that a block covers (60 degrees)
+const BLOCK_ARC_COS: f32 = 0.5;
+/// Time after starting a block during which a hit is parried (200ms)
+const PARRY_WINDOW_US: i64 = 200_000;
+/// Minimum time between lowering a block and raising it again (500ms)
+/// Keeps clients from re-raising their guard constantly to stay inside the parry window
+const BLOCK_COOLDOWN_US: i64 = 500_000;
+/// How long a parried attacker is staggered (1 second)
+const STAGGER_DURATION_US: i64 = 1_000_000;
+/// Time after starting a dodge during which hits are ignored (400ms)
+const DODGE_INVULNERABILITY_US: i64 = 400_000;
+/// Minimum time between dodges (800ms)
+const DODGE_COOLDOWN_US: i64 = 800_000;
+/// How much harder than the attack damage stat a heavy attack can hit
+pub const HEAVY_ATTACK_DAMAGE_MULTIPLIER: f32 = 1.5;
+
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbDamageType {
+    Slash,
+    Blunt,
+    Pierce,
+    Fire,
+    Magic,
+}
+
+impl DbDamageType {
+    /// Physical damage is reduced by armor as well as resistances
+    pub fn is_physical(&self) -> bool {
+        matches!(
+            self,
+            DbDamageType::Slash | DbDamageType::Blunt | DbDamageType::Pierce
+        )
+    }
+}
+
+/// Fractional damage reduction per damage type (0.25 = 25% less, negative = weakness)
+#[derive(SpacetimeType, Clone, Debug, Default, PartialEq)]
+pub struct DbResistances {
+    pub slash: f32,
+    pub blunt: f32,
+    pub pierce: f32,
+    pub fire: f32,
+    pub magic: f32,
+}
+
+impl DbResistances {
+    /// Get the resistance against a single damage type
+    pub fn get(&self, damage_type: DbDamageType) -> f32 {
+        match damage_type {
+            DbDamageType::Slash => self.slash,
+            DbDamageType::Blunt => self.blunt,
+            DbDamageType::Pierce => self.pierce,
+            DbDamageType::Fire => self.fire,
+            DbDamageType::Magic => self.magic,
+        }
+    }
+
+    /// Sum resistances from two sources
+    pub fn add(&self, other: &DbResistances) -> DbResistances {
+        DbResistances {
+            slash: self.slash + other.slash,
+            blunt: self.blunt + other.blunt,
+            pierce: self.pierce + other.pierce,
+            fire: self.fire + other.fire,
+            magic: self.magic + other.magic,
+        }
+    }
+
+    /// Whether every resistance is a finite number (not NaN or infinite)
+    pub fn is_finite(&self) -> bool {
+        [self.slash, self.blunt, self.pierce, self.fire, self.magic]
+            .iter()
+            .all(|value| value.is_finite())
+    }
+
+    /// Multiply every resistance by a scalar
+    pub fn scale(&self, factor: f32) -> DbResistances {
+        DbResistances {
+            slash: self.slash * factor,
+            blunt: self.blunt * factor,
+            pierce: self.pierce * factor,
+            fire: self.fire * factor,
+            magic: self.magic * factor,
+        }
+    }
+}
+
+/// Calculate the damage left after armor and resistances
+/// Armor reduces physical damage by armor / (armor + ARMOR_HALVING_VALUE), then the
+/// resistance for the damage type (clamped to [MIN_RESISTANCE, MAX_RESISTANCE]) applies
+pub fn mitigate_damage(
+    raw_damage: f32,
+    damage_type: DbDamageType,
+    armor: f32,
+    resistances: &DbResistances,
+) -> f32 {
+    if raw_damage <= 0.0 {
+        return 0.0;
+    }
+
+    let armor_multiplier = if damage_type.is_physical() {
+        ARMOR_HALVING_VALUE / (ARMOR_HALVING_VALUE + armor.max(0.0))
+    } else {
+        1.0
+    };
+    let resistance = resistances
+        .get(damage_type)
+        .clamp(MIN_RESISTANCE, MAX_RESISTANCE);
+
+    raw_damage * armor_multiplier * (1.0 - resistance)
+}
+
+/// How an incoming hit was met by the target's defensive state
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DefenseOutcome {
+    /// No defense applied, full damage
+    Hit,
+    /// Blocked from the front, damage reduced
+    Blocked,
+    /// Blocked within the parry window, no damage and the attacker is staggered
+    Parried,
+    /// Inside dodge invulnerability frames, no damage
+    Dodged,
+}
+
+impl DefenseOutcome {
+    /// Multiplier applied to damage for this outcome
+    pub fn damage_multiplier(&self) -> f32 {
+        match self {
+            DefenseOutcome::Hit => 1.0,
+            DefenseOutcome::Blocked => 1.0 - BLOCK_DAMAGE_REDUCTION,
+            DefenseOutcome::Parried | DefenseOutcome::Dodged => 0.0,
+        }
+    }
+}
+
+/// Check whether an entity is currently staggered
+pub fn is_staggered(entity: &Entity, current_time: i64) -> bool {
+    current_time < entity.staggered_until_us
+}
+
+/// Check whether an entity is inside its dodge invulnerability frames
+pub fn is_dodging(entity: &Entity, current_time: i64) -> bool {
+    entity.dodge_started_at_us > 0
+        && current_time - entity.dodge_started_at_us < DODGE_INVULNERABILITY_US
+}
+
+/// Check whether an entity may raise its guard
+pub fn block_can_start(entity: &Entity, current_time: i64) -> Result<(), String> {
+    if entity.block_started_at_us != 0 {
+        return Err("Already blocking".to_string());
+    }
+    if entity.block_ended_at_us > 0 && current_time - entity.block_ended_at_us < BLOCK_COOLDOWN_US {
+        return Err("Block is on cooldown".to_string());
+    }
+    Ok(())
+}
+
+/// Lower an entity's guard, starting the block cooldown if it was blocking
+pub fn block_end(entity: &mut Entity, current_time: i64) {
+    if entity.block_started_at_us != 0 {
+        entity.block_started_at_us = 0;
+        entity.block_ended_at_us = current_time;
+    }
+}
+
+/// Work out how the target's block or dodge affects a hit coming from attacker_position
+/// Blocks only cover an arc in front of the target (based on its Y rotation)
+pub fn resolve_defense(
+    target: &Entity,
+    attacker_position: &DbVector3,
+    current_time: i64,
+) -> DefenseOutcome {
+    if is_dodging(target, current_time) {
+        return DefenseOutcome::Dodged;
+    }
+
+    if target.block_started_at_us == 0 || is_staggered(target, current_time) {
+        return DefenseOutcome::Hit;
+    }
+
+    let yaw = target.rotation.y.to_radians();
+    let to_attacker_x = attacker_position.x - target.position.x;
+    let to_attacker_z = attacker_position.z - target.position.z;
+    let to_attacker_length = (to_attacker_x.powi(2) + to_attacker_z.powi(2)).sqrt();
+
+    // An attacker standing on top of the target counts as in front
+    let facing = to_attacker_length <= f32::EPSILON
+        || (yaw.sin() * to_attacker_x + yaw.cos() * to_attacker_z) / to_attacker_length
+            >= BLOCK_ARC_COS;
+
+    if !facing {
+        DefenseOutcome::Hit
+    } else if current_time - target.block_started_at_us <= PARRY_WINDOW_US {
+        DefenseOutcome::Parried
+    } else {
+        DefenseOutcome::Blocked
+    }
+}
+
+/// Stagger an entity, interrupting any block
+pub fn combat_stagger(ctx: &ReducerContext, entity_id: u32) {
+    if let Some(mut entity) = ctx.db.entity().entity_id().find(entity_id) {
+        let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+        entity.staggered_until_us = current_time + STAGGER_DURATION_US;
+        block_end(&mut entity, current_time);
+        ctx.db.entity().entity_id().update(entity);
+        log::debug!("Entity {} staggered", entity_id);
+    }
+}
+
+/// Get the calling player's entity, checking they are online and able to act
+fn combat_get_ready_entity(ctx: &ReducerContext) -> Result<Entity, String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+
+    if !player.online {
+        return Err("Player is not online".to_string());
+    }
+
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    if is_staggered(&entity, ctx.timestamp.to_micros_since_unix_epoch()) {
+        return Err("Cannot act while staggered".to_string());
+    }
+
+    Ok(entity)
+}
+
+/// Player raises their guard
+/// Hits landing within the parry window of this call are parried
+/// The guard can't be raised again until the block cooldown after lowering it
+#[spacetimedb::reducer]
+pub fn combat_block_start(ctx: &ReducerContext) -> Result<(), String> {
+    let mut entity = combat_get_ready_entity(ctx)?;
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+
+    block_can_start(&entity, current_time)?;
+
+    entity.block_started_at_us = current_time;
+    ctx.db.entity().entity_id().update(entity);
+    Ok(())
+}
+
+/// Player lowers their guard
+#[spacetimedb::reducer]
+pub fn combat_block_stop(ctx: &ReducerContext) -> Result<(), String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+
+    let mut entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    block_end(&mut entity, ctx.timestamp.to_micros_since_unix_epoch());
+    ctx.db.entity().entity_id().update(entity);
+    Ok(())
+}
+
+/// Player dodges, becoming briefly invulnerable
+/// Dodging costs stamina and drops any block in progress
+#[spacetimedb::reducer]
+pub fn combat_dodge(ctx: &ReducerContext) -> Result<(), String> {
+    let mut entity = combat_get_ready_entity(ctx)?;
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+
+    if entity.dodge_started_at_us > 0
+        && current_time - entity.dodge_started_at_us < DODGE_COOLDOWN_US
+    {
+        return Err("Dodge is on cooldown".to_string());
+    }
+
+    stamina_spend(&mut entity, DODGE_STAMINA_COST, current_time)?;
+    entity.dodge_started_at_us = current_time;
+    block_end(&mut entity, current_time);
+    ctx.db.entity().entity_id().update(entity);
+    Ok(())
+}
+
+/// Get an entity's effective armor and resistances, including active status effects
+pub fn entity_defenses(ctx: &ReducerContext, entity: &Entity) -> (f32, DbResistances) {
+    let (armor_modifier, resistance_modifiers) =
+        status_effect_defense_modifiers(ctx, entity.entity_id);
+
+    (
+        entity.armor + armor_modifier,
+        entity.resistances.add(&resistance_modifiers),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::entity::DbEntityKind;
+
+    fn resist(damage_type: DbDamageType, value: f32) -> DbResistances {
+        let mut resistances = DbResistances::default();
+        match damage_type {
+            DbDamageType::Slash => resistances.slash = value,
+            DbDamageType::Blunt => resistances.blunt = value,
+            DbDamageType::Pierce => resistances.pierce = value,
+            DbDamageType::Fire => resistances.fire = value,
+            DbDamageType::Magic => resistances.magic = value,
+        }
+        resistances
+    }
+
+    #[test]
+    fn unmitigated_damage_passes_through() {
+        let damage = mitigate_damage(20.0, DbDamageType::Slash, 0.0, &DbResistances::default());
+        assert_eq!(damage, 20.0);
+    }
+
+    #[test]
+    fn armor_halves_physical_damage_at_halving_value() {
+        for damage_type in [
+            DbDamageType::Slash,
+            DbDamageType::Blunt,
+            DbDamageType::Pierce,
+        ] {
+            let damage = mitigate_damage(
+                20.0,
+                damage_type,
+                ARMOR_HALVING_VALUE,
+                &DbResistances::default(),
+            );
+            assert_eq!(damage, 10.0);
+        }
+    }
+
+    #[test]
+    fn armor_does_not_reduce_elemental_damage() {
+        for damage_type in [DbDamageType::Fire, DbDamageType::Magic] {
+            let damage = mitigate_damage(20.0, damage_type, 500.0, &DbResistances::default());
+            assert_eq!(damage, 20.0);
+        }
+    }
+
+    #[test]
+    fn negative_armor_is_ignored() {
+        let damage = mitigate_damage(20.0, DbDamageType::Blunt, -50.0, &DbResistances::default());
+        assert_eq!(damage, 20.0);
+    }
+
+    #[test]
+    fn resistance_only_applies_to_its_damage_type() {
+        let resistances = resist(DbDamageType::Fire, 0.5);
+        assert_eq!(
+            mitigate_damage(20.0, DbDamageType::Fire, 0.0, &resistances),
+            10.0
+        );
+        assert_eq!(
+            mitigate_damage(20.0, DbDamageType::Magic, 0.0, &resistances),
+            20.0
+        );
+    }
+
+    #[test]
+    fn resistance_is_capped() {
+        let resistances = resist(DbDamageType::Magic, 5.0);
+        let damage = mitigate_damage(100.0, DbDamageType::Magic, 0.0, &resistances);
+        assert!((damage - 100.0 * (1.0 - MAX_RESISTANCE)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn weakness_increases_damage_up_to_double() {
+        assert_eq!(
+            mitigate_damage(
+                20.0,
+                DbDamageType::Pierce,
+                0.0,
+                &resist(DbDamageType::Pierce, -0.5)
+            ),
+            30.0
+        );
+        assert_eq!(
+            mitigate_damage(
+                20.0,
+                DbDamageType::Pierce,
+                0.0,
+                &resist(DbDamageType::Pierce, -3.0)
+            ),
+            40.0
+        );
+    }
+
+    #[test]
+    fn armor_and_resistance_stack_multiplicatively() {
+        let resistances = resist(DbDamageType::Slash, 0.5);
+        let damage = mitigate_damage(40.0, DbDamageType::Slash, ARMOR_HALVING_VALUE, &resistances);
+        assert_eq!(damage, 10.0);
+    }
+
+    #[test]
+    fn non_positive_damage_is_zero() {
+        assert_eq!(
+            mitigate_damage(-15.0, DbDamageType::Slash, 0.0, &DbResistances::default()),
+            0.0
+        );
+        assert_eq!(
+            mitigate_damage(0.0, DbDamageType::Fire, 0.0, &DbResistances::default()),
+            0.0
+        );
+    }
+
+    #[test]
+    fn mitigation_at_the_limits() {
+        // (damage type, armor, resistance, expected damage from 100)
+        let cases = [
+            // Resistance is clamped at MIN_RESISTANCE (double damage)
+            (DbDamageType::Blunt, 0.0, -1.0, 200.0),
+            (DbDamageType::Blunt, 0.0, -1.5, 200.0),
+            // Resistance is clamped at MAX_RESISTANCE (90% reduction)
+            (DbDamageType::Fire, 0.0, 0.9, 10.0),
+            (DbDamageType::Fire, 0.0, 1.0, 10.0),
+            // Zero armor leaves physical damage untouched
+            (DbDamageType::Slash, 0.0, 0.0, 100.0),
+            (DbDamageType::Pierce, 0.0, 0.25, 75.0),
+            // Armor never reduces non-physical damage, with or without resistance
+            (DbDamageType::Fire, 1000.0, 0.0, 100.0),
+            (DbDamageType::Magic, 1000.0, 0.5, 50.0),
+            (DbDamageType::Magic, 1000.0, -1.0, 200.0),
+        ];
+        for (damage_type, armor, resistance, expected) in cases {
+            let damage =
+                mitigate_damage(100.0, damage_type, armor, &resist(damage_type, resistance));
+            assert!(
+                (damage - expected).abs() < 1e-3,
+                "{:?} with armor {} and resistance {}: expected {}, got {}",
+                damage_type,
+                armor,
+                resistance,
+                expected,
+                damage
+            );
+        }
+    }
+
+    fn entity_facing_north(block_started_at_us: i64, dodge_started_at_us: i64) -> Entity {
+        Entity {
+            entity_id: 1,
+            kind: DbEntityKind::Player,
+            faction_id: 0,
+            position: DbVector3::default(),
+            rotation: DbVector3::default(),
+            health: 100.0,
+            max_health: 100.0,
+            attack_range: 3.0,
+            attack_damage: 10.0,
+            armor: 0.0,
+            resistances: DbResistances::default(),
+            block_started_at_us,
+            block_ended_at_us: 0,
+            dodge_started_at_us,
+            staggered_until_us: 0,
+            stamina: 100.0,
+            max_stamina: 100.0,
+            stamina_regen_per_sec: 20.0,
+            last_stamina_use_us: 0,
+        }
+    }
+
+    const IN_FRONT: DbVector3 = DbVector3 {
+        x: 0.0,
+        y: 0.0,
+        z: 2.0,
+    };
+    const BEHIND: DbVector3 = DbVector3 {
+        x: 0.0,
+        y: 0.0,
+        z: -2.0,
+    };
+
+    #[test]
+    fn unguarded_target_is_hit() {
+        let target = entity_facing_north(0, 0);
+        assert_eq!(
+            resolve_defense(&target, &IN_FRONT, 10_000_000),
+            DefenseOutcome::Hit
+        );
+    }
+
+    #[test]
+    fn hit_inside_dodge_frames_is_dodged() {
+        let target = entity_facing_north(0, 10_000_000);
+        assert_eq!(
+            resolve_defense(&target, &BEHIND, 10_000_000 + DODGE_INVULNERABILITY_US - 1),
+            DefenseOutcome::Dodged
+        );
+        assert_eq!(
+            resolve_defense(&target, &BEHIND, 10_000_000 + DODGE_INVULNERABILITY_US),
+            DefenseOutcome::Hit
+        );
+    }
+
+    #[test]
+    fn block_inside_parry_window_parries() {
+        let target = entity_facing_north(10_000_000, 0);
+        assert_eq!(
+            resolve_defense(&target, &IN_FRONT, 10_000_000 + PARRY_WINDOW_US),
+            DefenseOutcome::Parried
+        );
+        assert_eq!(
+            resolve_defense(&target, &IN_FRONT, 10_000_000 + PARRY_WINDOW_US + 1),
+            DefenseOutcome::Blocked
+        );
+    }
+
+    #[test]
+    fn block_does_not_cover_attacks_from_behind() {
+        let target = entity_facing_north(10_000_000, 0);
+        assert_eq!(
+            resolve_defense(&target, &BEHIND, 12_000_000),
+            DefenseOutcome::Hit
+        );
+    }
+
+    #[test]
+    fn block_follows_target_rotation() {
+        let mut target = entity_facing_north(10_000_000, 0);
+        target.rotation.y = 180.0;
+        assert_eq!(
+            resolve_defense(&target, &BEHIND, 12_000_000),
+            DefenseOutcome::Blocked
+        );
+    }
+
+    #[test]
+    fn staggered_target_cannot_block() {
+        let mut target = entity_facing_north(10_000_000, 0);
+        target.staggered_until_us = 13_000_000;
+        assert_eq!(
+            resolve_defense(&target, &IN_FRONT, 12_000_000),
+            DefenseOutcome::Hit
+        );
+    }
+
+    #[test]
+    fn block_cannot_be_restarted_during_cooldown() {
+        let mut target = entity_facing_north(10_000_000, 0);
+        block_end(&mut target, 10_150_000);
+        assert_eq!(target.block_started_at_us, 0);
+
+        // Toggling the guard quickly would otherwise re-arm the parry window
+        assert!(block_can_start(&target, 10_300_000).is_err());
+        assert!(block_can_start(&target, 10_150_000 + BLOCK_COOLDOWN_US - 1).is_err());
+        assert_eq!(
+            block_can_start(&target, 10_150_000 + BLOCK_COOLDOWN_US),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn first_block_has_no_cooldown() {
+        let target = entity_facing_north(0, 0);
+        assert_eq!(block_can_start(&target, 100), Ok(()));
+        assert!(block_can_start(&entity_facing_north(100, 0), 200).is_err());
+    }
+}
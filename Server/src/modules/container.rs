@@ -0,0 +1,268 @@
+use crate::modules::admin::is_admin;
+use crate::modules::building_piece_placed::{building_piece_placed, DbBuildingPiecePlaced};
+use crate::modules::dropped_item::dropped_item_spawn;
+use crate::modules::encumbrance::encumbrance_check_loot;
+use crate::modules::entity::entity;
+use crate::modules::inventory::{
+    inventory_add_stack_internal, inventory_remove_item_internal, item_max_stack, ItemRef,
+    ItemSlots,
+};
+use crate::modules::player::player;
+use spacetimedb::{Identity, ReducerContext, Table};
+
+/// Maximum distance from a container's piece position to deposit or withdraw
+const CONTAINER_RANGE: f32 = 3.0;
+
+/// Storage owned by a placed container piece (e.g., a chest)
+#[spacetimedb::table(name = container, public)]
+pub struct Container {
+    /// References DbBuildingPiecePlaced.piece_id
+    #[primary_key]
+    pub piece_id: u32,
+    pub size: u32,
+    /// One entry per slot (`size` entries), None for an empty slot
+    pub slots: Vec<Option<ItemRef>>,
+    /// Players other than the owner who may deposit and withdraw
+    pub shared_with: Vec<Identity>,
+}
+
+impl ItemSlots for Container {
+    fn slots(&self) -> &[Option<ItemRef>] {
+        &self.slots
+    }
+
+    fn slots_mut(&mut self) -> &mut [Option<ItemRef>] {
+        &mut self.slots
+    }
+}
+
+/// Give a newly placed container piece its storage
+pub fn container_create(ctx: &ReducerContext, piece_id: u32, size: u32) {
+    ctx.db.container().insert(Container {
+        piece_id,
+        size,
+        slots: vec![None; size as usize],
+        shared_with: Vec::new(),
+    });
+}
+
+/// Fail if a container piece still holds items (it must be emptied before removal)
+pub fn container_check_empty(ctx: &ReducerContext, piece_id: u32) -> Result<(), String> {
+    match ctx.db.container().piece_id().find(piece_id) {
+        Some(container) if container.slots.iter().any(Option::is_some) => {
+            Err("Empty the container before removing it".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Remove a container's storage, spilling its contents where the piece stood
+pub fn container_on_piece_removed(ctx: &ReducerContext, piece: &DbBuildingPiecePlaced) {
+    let container = match ctx.db.container().piece_id().find(piece.piece_id) {
+        Some(container) => container,
+        None => return,
+    };
+
+    for stack in container.slots.into_iter().flatten() {
+        if let Err(err) = dropped_item_spawn(ctx, stack, piece.position.clone(), piece.owner) {
+            log::warn!("Failed to spill container {}: {}", piece.piece_id, err);
+        }
+    }
+    ctx.db.container().piece_id().delete(piece.piece_id);
+}
+
+/// Get a container the caller may use, checking access and range
+fn container_get_accessible(
+    ctx: &ReducerContext,
+    piece_id: u32,
+) -> Result<(Container, DbBuildingPiecePlaced), String> {
+    let piece = ctx
+        .db
+        .building_piece_placed()
+        .piece_id()
+        .find(piece_id)
+        .ok_or("Building piece not found")?;
+    let container = ctx
+        .db
+        .container()
+        .piece_id()
+        .find(piece_id)
+        .ok_or("Building piece is not a container")?;
+
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+    container_check_access(
+        piece.owner,
+        &container.shared_with,
+        ctx.sender,
+        is_admin(ctx, ctx.sender),
+        entity.position.distance(&piece.position),
+    )?;
+
+    Ok((container, piece))
+}
+
+/// Decide whether a player may use a container: they must be its owner, shared with or an
+/// admin, and within range of the piece
+fn container_check_access(
+    owner: Identity,
+    shared_with: &[Identity],
+    user: Identity,
+    user_is_admin: bool,
+    distance: f32,
+) -> Result<(), String> {
+    if user != owner && !shared_with.contains(&user) && !user_is_admin {
+        return Err("You don't have access to this container".to_string());
+    }
+    if distance > CONTAINER_RANGE {
+        return Err(format!(
+            "Too far away from the container. Distance: {:.1}, Range: {:.1}",
+            distance, CONTAINER_RANGE
+        ));
+    }
+    Ok(())
+}
+
+/// Move items from the caller's inventory into a container
+#[spacetimedb::reducer]
+pub fn container_deposit(
+    ctx: &ReducerContext,
+    piece_id: u32,
+    item_id: u32,
+    quantity: u32,
+) -> Result<(), String> {
+    let (mut container, _) = container_get_accessible(ctx, piece_id)?;
+
+    let max_stack = item_max_stack(ctx, item_id)?;
+    for item in inventory_remove_item_internal(ctx, ctx.sender, item_id, quantity)? {
+        container.add_stack(&item, max_stack)?;
+    }
+    ctx.db.container().piece_id().update(container);
+    Ok(())
+}
+
+/// Move items from a container into the caller's inventory
+#[spacetimedb::reducer]
+pub fn container_withdraw(
+    ctx: &ReducerContext,
+    piece_id: u32,
+    item_id: u32,
+    quantity: u32,
+) -> Result<(), String> {
+    let (mut container, _) = container_get_accessible(ctx, piece_id)?;
+
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    encumbrance_check_loot(ctx, &player)?;
+
+    for item in container.remove_item(item_id, quantity)? {
+        inventory_add_stack_internal(ctx, ctx.sender, &item)?;
+    }
+    ctx.db.container().piece_id().update(container);
+    Ok(())
+}
+
+/// Let another player use a container (owner only)
+#[spacetimedb::reducer]
+pub fn container_share(
+    ctx: &ReducerContext,
+    piece_id: u32,
+    identity: Identity,
+) -> Result<(), String> {
+    let mut container = container_get_owned(ctx, piece_id)?;
+    if !container.shared_with.contains(&identity) {
+        container.shared_with.push(identity);
+        ctx.db.container().piece_id().update(container);
+    }
+    Ok(())
+}
+
+/// Stop another player from using a container (owner only)
+#[spacetimedb::reducer]
+pub fn container_unshare(
+    ctx: &ReducerContext,
+    piece_id: u32,
+    identity: Identity,
+) -> Result<(), String> {
+    let mut container = container_get_owned(ctx, piece_id)?;
+    container.shared_with.retain(|shared| *shared != identity);
+    ctx.db.container().piece_id().update(container);
+    Ok(())
+}
+
+fn container_get_owned(ctx: &ReducerContext, piece_id: u32) -> Result<Container, String> {
+    let piece = ctx
+        .db
+        .building_piece_placed()
+        .piece_id()
+        .find(piece_id)
+        .ok_or("Building piece not found")?;
+    if piece.owner != ctx.sender {
+        return Err("Only the owner can change who may use this container".to_string());
+    }
+    ctx.db
+        .container()
+        .piece_id()
+        .find(piece_id)
+        .ok_or("Building piece is not a container".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(byte: u8) -> Identity {
+        Identity::from_byte_array([byte; 32])
+    }
+
+    #[test]
+    fn owner_in_range_has_access() {
+        let owner = identity(1);
+        assert!(container_check_access(owner, &[], owner, false, 1.0).is_ok());
+    }
+
+    #[test]
+    fn shared_players_have_access() {
+        let guest = identity(2);
+        assert!(container_check_access(identity(1), &[guest], guest, false, 1.0).is_ok());
+    }
+
+    #[test]
+    fn strangers_are_denied() {
+        let result = container_check_access(identity(1), &[identity(2)], identity(3), false, 1.0);
+        assert_eq!(
+            result,
+            Err("You don't have access to this container".to_string())
+        );
+    }
+
+    #[test]
+    fn admins_have_access() {
+        assert!(container_check_access(identity(1), &[], identity(3), true, 1.0).is_ok());
+    }
+
+    #[test]
+    fn access_ends_at_the_container_range() {
+        let owner = identity(1);
+        assert!(container_check_access(owner, &[], owner, false, CONTAINER_RANGE).is_ok());
+        assert!(container_check_access(owner, &[], owner, false, CONTAINER_RANGE + 0.1).is_err());
+        // Admins still have to walk up to the container
+        assert!(
+            container_check_access(owner, &[], identity(3), true, CONTAINER_RANGE + 0.1).is_err()
+        );
+    }
+}
@@ -0,0 +1,565 @@
+use crate::modules::admin::require_admin;
+use crate::modules::building_piece_placed::{building_piece_placed, DbBuildingPieceType};
+use crate::modules::building_piece_variant::building_piece_variant_get;
+use crate::modules::dropped_item::dropped_item_spawn;
+use crate::modules::entity::entity;
+use crate::modules::inventory::{
+    inventory_add_stack_partial, inventory_remove_item_internal, item_new_stack, ItemRef,
+};
+use crate::modules::item_definition::item_definition_check_all;
+use crate::modules::player::player;
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often finished crafts are handed out (250ms)
+const CRAFTING_TICK_INTERVAL_US: i64 = 250_000;
+/// Maximum distance to a recipe's crafting station
+const CRAFTING_STATION_RANGE: f32 = 4.0;
+/// Most crafts a player can have queued at once
+const CRAFTING_MAX_QUEUE: usize = 10;
+/// Longest time a single craft can take (1 day)
+const CRAFTING_MAX_CRAFT_TIME_SECONDS: f32 = 86_400.0;
+/// Crafting experience needed per skill level
+const CRAFTING_EXPERIENCE_PER_LEVEL: u32 = 100;
+
+/// How to turn some items into others
+#[spacetimedb::table(name = recipe, public)]
+pub struct Recipe {
+    #[primary_key]
+    pub recipe_id: u32,
+    pub name: String,
+    /// Items consumed when the craft starts
+    pub inputs: Vec<ItemRef>,
+    /// Items granted when the craft completes
+    pub outputs: Vec<ItemRef>,
+    pub craft_time_us: i64,
+    /// Building piece variant the player must stand near (None = craft anywhere)
+    pub required_station_variant_id: Option<u32>,
+    pub required_skill_level: u32,
+    /// Crafting experience granted on completion
+    pub experience: u32,
+}
+
+/// A craft waiting in or being worked on in a player's queue
+#[spacetimedb::table(name = craft_job, public)]
+pub struct CraftJob {
+    #[primary_key]
+    #[auto_inc]
+    pub job_id: u32,
+    #[index(btree)]
+    pub identity: Identity,
+    /// References Recipe.recipe_id
+    pub recipe_id: u32,
+    /// Inputs taken from the inventory, refunded on cancel
+    pub reserved_inputs: Vec<ItemRef>,
+    pub started_at_us: i64,
+    #[index(btree)]
+    pub completes_at_us: i64,
+}
+
+/// A player's crafting progress
+#[spacetimedb::table(name = crafting_skill, public)]
+pub struct CraftingSkill {
+    #[primary_key]
+    pub identity: Identity,
+    pub level: u32,
+    pub experience: u32,
+}
+
+/// Schedule driving `crafting_tick`
+#[spacetimedb::table(name = crafting_schedule, scheduled(crafting_tick))]
+pub struct CraftingSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn recipe_insert(
+    ctx: &ReducerContext,
+    recipe_id: u32,
+    name: &str,
+    inputs: Vec<ItemRef>,
+    outputs: Vec<ItemRef>,
+    craft_time_us: i64,
+    required_station_variant_id: Option<u32>,
+    required_skill_level: u32,
+    experience: u32,
+) {
+    ctx.db.recipe().insert(Recipe {
+        recipe_id,
+        name: name.to_string(),
+        inputs,
+        outputs,
+        craft_time_us,
+        required_station_variant_id,
+        required_skill_level,
+        experience,
+    });
+}
+
+/// Initialize default recipes and start the crafting schedule
+pub fn crafting_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Arrows - recipe_id 0
+    recipe_insert(
+        ctx,
+        0,
+        "Arrows",
+        vec![ItemRef::new(0, 2), ItemRef::new(1, 1)], // Branch, Rock
+        vec![ItemRef::new(3, 10)],                    // Arrow
+        2_000_000,                                    // 2 seconds
+        None,
+        1,
+        10,
+    );
+
+    // Wooden Club - recipe_id 1
+    recipe_insert(
+        ctx,
+        1,
+        "Wooden Club",
+        vec![ItemRef::new(2, 3)], // Wood
+        vec![ItemRef::new(5, 1)], // Wooden Club
+        5_000_000,                // 5 seconds
+        Some(15),                 // Workbench
+        1,
+        25,
+    );
+
+    // Hide Cap - recipe_id 2
+    recipe_insert(
+        ctx,
+        2,
+        "Hide Cap",
+        vec![ItemRef::new(4, 3)], // Hide
+        vec![ItemRef::new(6, 1)], // Hide Cap
+        5_000_000,                // 5 seconds
+        Some(15),                 // Workbench
+        2,
+        30,
+    );
+
+    // Hide Boots - recipe_id 3
+    recipe_insert(
+        ctx,
+        3,
+        "Hide Boots",
+        vec![ItemRef::new(4, 4)], // Hide
+        vec![ItemRef::new(8, 1)], // Hide Boots
+        6_000_000,                // 6 seconds
+        Some(15),                 // Workbench
+        2,
+        35,
+    );
+
+    // Hide Tunic - recipe_id 4
+    recipe_insert(
+        ctx,
+        4,
+        "Hide Tunic",
+        vec![ItemRef::new(4, 6)], // Hide
+        vec![ItemRef::new(7, 1)], // Hide Tunic
+        10_000_000,               // 10 seconds
+        Some(15),                 // Workbench
+        3,
+        50,
+    );
+
+    log::info!("Initialized default recipes");
+
+    ctx.db.crafting_schedule().insert(CraftingSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(CRAFTING_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+/// A player's crafting skill level (1 before their first craft)
+pub fn crafting_skill_level(ctx: &ReducerContext, identity: Identity) -> u32 {
+    ctx.db
+        .crafting_skill()
+        .identity()
+        .find(identity)
+        .map_or(1, |skill| skill.level)
+}
+
+fn crafting_add_experience(ctx: &ReducerContext, identity: Identity, experience: u32) {
+    let existing = ctx.db.crafting_skill().identity().find(identity);
+    let is_new = existing.is_none();
+    let mut skill = existing.unwrap_or(CraftingSkill {
+        identity,
+        level: 1,
+        experience: 0,
+    });
+
+    skill.experience = skill.experience.saturating_add(experience);
+    let level = 1 + skill.experience / CRAFTING_EXPERIENCE_PER_LEVEL;
+    if level > skill.level {
+        log::info!("Player {} reached crafting level {}", identity, level);
+        skill.level = level;
+    }
+
+    if is_new {
+        ctx.db.crafting_skill().insert(skill);
+    } else {
+        ctx.db.crafting_skill().identity().update(skill);
+    }
+}
+
+/// Fail unless the sender stands near a placed piece of the station variant
+fn crafting_check_station(ctx: &ReducerContext, variant_id: u32) -> Result<(), String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    let near_station = ctx
+        .db
+        .building_piece_placed()
+        .variant_id()
+        .filter(variant_id)
+        .any(|piece| entity.position.distance(&piece.position) <= CRAFTING_STATION_RANGE);
+    if !near_station {
+        let variant = building_piece_variant_get(ctx, variant_id)?;
+        return Err(format!("Requires a nearby {}", variant.variant_name));
+    }
+    Ok(())
+}
+
+/// Re-time a player's queued crafts so each starts when the previous one finishes
+fn crafting_repack_queue(ctx: &ReducerContext, identity: Identity) {
+    let mut jobs: Vec<_> = ctx.db.craft_job().identity().filter(identity).collect();
+    crafting_repack_jobs(&mut jobs, ctx.timestamp.to_micros_since_unix_epoch());
+
+    for job in jobs {
+        ctx.db.craft_job().job_id().update(job);
+    }
+}
+
+/// Sort jobs into queue order and move every job that hasn't started yet up behind the one
+/// before it, keeping its duration (jobs already in progress keep their timing)
+fn crafting_repack_jobs(jobs: &mut [CraftJob], current_time: i64) {
+    jobs.sort_by_key(|job| job.completes_at_us);
+
+    let mut next_start = current_time;
+    for job in jobs {
+        if job.started_at_us > current_time {
+            let duration = job.completes_at_us - job.started_at_us;
+            job.started_at_us = next_start;
+            job.completes_at_us = next_start + duration;
+        }
+        next_start = next_start.max(job.completes_at_us);
+    }
+}
+
+/// Queue a craft, taking its inputs from the inventory right away
+#[spacetimedb::reducer]
+pub fn craft_start(ctx: &ReducerContext, recipe_id: u32) -> Result<(), String> {
+    let recipe = ctx
+        .db
+        .recipe()
+        .recipe_id()
+        .find(recipe_id)
+        .ok_or("Recipe not found")?;
+
+    let level = crafting_skill_level(ctx, ctx.sender);
+    if level < recipe.required_skill_level {
+        return Err(format!(
+            "Requires crafting level {} (you are level {})",
+            recipe.required_skill_level, level
+        ));
+    }
+    if let Some(variant_id) = recipe.required_station_variant_id {
+        crafting_check_station(ctx, variant_id)?;
+    }
+
+    let queued: Vec<_> = ctx.db.craft_job().identity().filter(ctx.sender).collect();
+    if queued.len() >= CRAFTING_MAX_QUEUE {
+        return Err("Crafting queue is full".to_string());
+    }
+
+    let mut reserved_inputs = Vec::new();
+    for input in &recipe.inputs {
+        reserved_inputs.extend(inventory_remove_item_internal(
+            ctx,
+            ctx.sender,
+            input.id,
+            input.quantity,
+        )?);
+    }
+
+    // Start once everything already queued is done
+    let started_at_us = queued
+        .iter()
+        .map(|job| job.completes_at_us)
+        .fold(ctx.timestamp.to_micros_since_unix_epoch(), i64::max);
+    let job = ctx.db.craft_job().insert(CraftJob {
+        job_id: 0,
+        identity: ctx.sender,
+        recipe_id,
+        reserved_inputs,
+        started_at_us,
+        completes_at_us: started_at_us + recipe.craft_time_us,
+    });
+
+    log::info!(
+        "Player {} queued {} as craft {}",
+        ctx.sender,
+        recipe.name,
+        job.job_id
+    );
+    Ok(())
+}
+
+/// Cancel a queued craft and get its inputs back (whatever doesn't fit is dropped)
+#[spacetimedb::reducer]
+pub fn craft_cancel(ctx: &ReducerContext, job_id: u32) -> Result<(), String> {
+    let job = ctx
+        .db
+        .craft_job()
+        .job_id()
+        .find(job_id)
+        .ok_or("Craft not found")?;
+    if job.identity != ctx.sender {
+        return Err("This craft is not yours".to_string());
+    }
+
+    ctx.db.craft_job().job_id().delete(job_id);
+    for input in &job.reserved_inputs {
+        crafting_give(ctx, ctx.sender, input);
+    }
+    crafting_repack_queue(ctx, ctx.sender);
+
+    log::info!("Player {} cancelled craft {}", ctx.sender, job_id);
+    Ok(())
+}
+
+/// Put items into a player's inventory, dropping whatever doesn't fit at their feet
+fn crafting_give(ctx: &ReducerContext, identity: Identity, item: &ItemRef) {
+    let overflow = match inventory_add_stack_partial(ctx, identity, item) {
+        Ok(overflow) => overflow,
+        Err(err) => {
+            log::warn!("Failed to give crafting items to {}: {}", identity, err);
+            item.quantity
+        }
+    };
+    if overflow == 0 {
+        return;
+    }
+
+    log::info!("Dropping {} of item {} for {}", overflow, item.id, identity);
+    let position = ctx
+        .db
+        .player()
+        .identity()
+        .find(identity)
+        .and_then(|player| ctx.db.entity().entity_id().find(player.entity_id))
+        .map(|entity| entity.position);
+    let dropped = position
+        .ok_or("Player entity not found".to_string())
+        .and_then(|position| {
+            dropped_item_spawn(
+                ctx,
+                ItemRef {
+                    quantity: overflow,
+                    ..item.clone()
+                },
+                position,
+                identity,
+            )
+        });
+    if let Err(err) = dropped {
+        log::warn!(
+            "Lost {} of item {} given to {}: {}",
+            overflow,
+            item.id,
+            identity,
+            err
+        );
+    }
+}
+
+/// Give a finished craft's outputs, dropping whatever doesn't fit at the player's feet
+fn crafting_complete(ctx: &ReducerContext, job: CraftJob) {
+    ctx.db.craft_job().job_id().delete(job.job_id);
+
+    let recipe = match ctx.db.recipe().recipe_id().find(job.recipe_id) {
+        Some(recipe) => recipe,
+        None => {
+            log::warn!(
+                "Recipe {} of craft {} no longer exists",
+                job.recipe_id,
+                job.job_id
+            );
+            return;
+        }
+    };
+
+    for output in recipe.outputs {
+        let output = match item_new_stack(ctx, output.id, output.quantity) {
+            Ok(output) => output,
+            Err(err) => {
+                log::warn!("Lost craft output of {}: {}", job.identity, err);
+                continue;
+            }
+        };
+        crafting_give(ctx, job.identity, &output);
+    }
+
+    crafting_add_experience(ctx, job.identity, recipe.experience);
+    log::info!("Player {} crafted {}", job.identity, recipe.name);
+}
+
+/// Complete every craft whose time is up
+#[spacetimedb::reducer]
+pub fn crafting_tick(ctx: &ReducerContext, _schedule: CraftingSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `crafting_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let finished: Vec<_> = ctx
+        .db
+        .craft_job()
+        .completes_at_us()
+        .filter(..=current_time)
+        .collect();
+    for job in finished {
+        crafting_complete(ctx, job);
+    }
+    Ok(())
+}
+
+/// Creates a new recipe (admin only)
+#[spacetimedb::reducer]
+#[allow(clippy::too_many_arguments)]
+pub fn recipe_create(
+    ctx: &ReducerContext,
+    recipe_id: u32,
+    name: String,
+    inputs: Vec<ItemRef>,
+    outputs: Vec<ItemRef>,
+    craft_time_seconds: f32,
+    required_station_variant_id: Option<u32>,
+    required_skill_level: u32,
+    experience: u32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx.db.recipe().recipe_id().find(recipe_id).is_some() {
+        return Err(format!("Recipe {} already exists", recipe_id));
+    }
+    if outputs.is_empty() {
+        return Err("A recipe needs at least one output".to_string());
+    }
+    if !craft_time_seconds.is_finite()
+        || !(0.0..=CRAFTING_MAX_CRAFT_TIME_SECONDS).contains(&craft_time_seconds)
+    {
+        return Err(format!(
+            "Craft time must be between 0 and {} seconds",
+            CRAFTING_MAX_CRAFT_TIME_SECONDS
+        ));
+    }
+    if inputs.iter().chain(&outputs).any(|item| item.quantity == 0) {
+        return Err("Recipe quantities must be positive".to_string());
+    }
+    item_definition_check_all(ctx, inputs.iter().chain(&outputs).map(|item| item.id))?;
+    if let Some(variant_id) = required_station_variant_id {
+        let variant = building_piece_variant_get(ctx, variant_id)?;
+        if !matches!(variant.piece_type, DbBuildingPieceType::CraftingStation) {
+            return Err(format!(
+                "{} is not a crafting station",
+                variant.variant_name
+            ));
+        }
+    }
+
+    ctx.db.recipe().insert(Recipe {
+        recipe_id,
+        name,
+        inputs,
+        outputs,
+        craft_time_us: (craft_time_seconds * 1_000_000.0) as i64,
+        required_station_variant_id,
+        required_skill_level,
+        experience,
+    });
+    log::info!("Created recipe with recipe_id: {}", recipe_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000_000;
+
+    fn job(job_id: u32, started_at_us: i64, completes_at_us: i64) -> CraftJob {
+        CraftJob {
+            job_id,
+            identity: Identity::ZERO,
+            recipe_id: 0,
+            reserved_inputs: Vec::new(),
+            started_at_us,
+            completes_at_us,
+        }
+    }
+
+    fn timings(jobs: &[CraftJob]) -> Vec<(u32, i64, i64)> {
+        jobs.iter()
+            .map(|job| (job.job_id, job.started_at_us, job.completes_at_us))
+            .collect()
+    }
+
+    #[test]
+    fn queued_jobs_close_the_gap_left_by_a_cancelled_job() {
+        // Job 2 (NOW + 100..NOW + 300) was cancelled from between jobs 1 and 3
+        let mut jobs = vec![job(3, NOW + 300, NOW + 400), job(1, NOW - 50, NOW + 100)];
+        crafting_repack_jobs(&mut jobs, NOW);
+
+        assert_eq!(
+            timings(&jobs),
+            vec![(1, NOW - 50, NOW + 100), (3, NOW + 100, NOW + 200)]
+        );
+    }
+
+    #[test]
+    fn job_in_progress_keeps_its_timing() {
+        let mut jobs = vec![job(1, NOW - 50, NOW + 100)];
+        crafting_repack_jobs(&mut jobs, NOW);
+
+        assert_eq!(timings(&jobs), vec![(1, NOW - 50, NOW + 100)]);
+    }
+
+    #[test]
+    fn next_job_starts_now_when_the_head_was_cancelled() {
+        let mut jobs = vec![job(2, NOW + 200, NOW + 500), job(3, NOW + 500, NOW + 600)];
+        crafting_repack_jobs(&mut jobs, NOW);
+
+        assert_eq!(
+            timings(&jobs),
+            vec![(2, NOW, NOW + 300), (3, NOW + 300, NOW + 400)]
+        );
+    }
+
+    #[test]
+    fn queue_without_gaps_is_unchanged() {
+        let mut jobs = vec![
+            job(1, NOW - 100, NOW + 100),
+            job(2, NOW + 100, NOW + 250),
+            job(3, NOW + 250, NOW + 300),
+        ];
+        let before = timings(&jobs);
+        crafting_repack_jobs(&mut jobs, NOW);
+
+        assert_eq!(timings(&jobs), before);
+    }
+}
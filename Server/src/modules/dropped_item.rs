@@ -0,0 +1,260 @@
+use crate::modules::admin::require_admin;
+use crate::modules::encumbrance::encumbrance_check_loot;
+use crate::modules::entity::{entity, entity_create, entity_delete, DbEntityKind};
+use crate::modules::inventory::{
+    inventory_add_stack_partial, inventory_remove_item_internal, ItemRef,
+};
+use crate::modules::line_of_sight::has_line_of_sight;
+use crate::modules::navmesh::{is_position_valid, navmesh_surface_height};
+use crate::modules::player::player;
+use crate::modules::zone::zone_check_loot;
+use crate::types::DbVector3;
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often expired dropped items are removed (1 second)
+const DROPPED_ITEM_DESPAWN_TICK_INTERVAL_US: i64 = 1_000_000;
+/// Distance in front of the player that items are dropped at
+const DROP_DISTANCE: f32 = 1.0;
+
+/// Item stack lying in the world that anyone nearby can pick up
+#[spacetimedb::table(name = dropped_item, public)]
+pub struct DroppedItem {
+    /// References Entity.entity_id (kind DroppedItem) holding the position
+    #[primary_key]
+    pub entity_id: u32,
+    pub item: ItemRef,
+    pub dropped_by: Identity,
+    pub dropped_at_us: i64,
+    /// Timestamp when the item is removed from the world
+    #[index(btree)]
+    pub despawn_at_us: i64,
+}
+
+/// Settings for dropped items (single row with id 0)
+#[spacetimedb::table(name = dropped_item_config, public)]
+pub struct DroppedItemConfig {
+    #[primary_key]
+    pub id: u32,
+    /// Time in microseconds before a dropped item disappears
+    pub despawn_after_us: i64,
+    /// Maximum distance from which a dropped item can be picked up
+    pub pickup_range: f32,
+}
+
+/// Schedule driving `dropped_item_despawn_tick`
+#[spacetimedb::table(name = dropped_item_despawn_schedule, scheduled(dropped_item_despawn_tick))]
+pub struct DroppedItemDespawnSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Create the default dropped item settings and start the despawn schedule
+pub fn dropped_item_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.dropped_item_config().insert(DroppedItemConfig {
+        id: 0,
+        despawn_after_us: 300_000_000, // 5 minutes
+        pickup_range: 2.0,
+    });
+    ctx.db
+        .dropped_item_despawn_schedule()
+        .insert(DroppedItemDespawnSchedule {
+            scheduled_id: 0,
+            scheduled_at: TimeDuration::from_micros(DROPPED_ITEM_DESPAWN_TICK_INTERVAL_US).into(),
+        });
+    Ok(())
+}
+
+fn dropped_item_get_config(ctx: &ReducerContext) -> Result<DroppedItemConfig, String> {
+    ctx.db
+        .dropped_item_config()
+        .id()
+        .find(0)
+        .ok_or("Dropped item config not found".to_string())
+}
+
+/// Spawn an item stack in the world
+pub fn dropped_item_spawn(
+    ctx: &ReducerContext,
+    item: ItemRef,
+    position: DbVector3,
+    dropped_by: Identity,
+) -> Result<DroppedItem, String> {
+    let config = dropped_item_get_config(ctx)?;
+    let entity = entity_create(
+        ctx,
+        DbEntityKind::DroppedItem,
+        position,
+        DbVector3::default(),
+        0.0,
+    )?;
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    Ok(ctx.db.dropped_item().insert(DroppedItem {
+        entity_id: entity.entity_id,
+        item,
+        dropped_by,
+        dropped_at_us: current_time,
+        despawn_at_us: current_time + config.despawn_after_us,
+    }))
+}
+
+fn dropped_item_delete(ctx: &ReducerContext, entity_id: u32) {
+    ctx.db.dropped_item().entity_id().delete(entity_id);
+    entity_delete(ctx, entity_id);
+}
+
+/// Drop items from the sender's inventory in front of them
+#[spacetimedb::reducer]
+pub fn item_drop(ctx: &ReducerContext, item_id: u32, quantity: u32) -> Result<(), String> {
+    if quantity == 0 {
+        return Err("Quantity must be positive".to_string());
+    }
+
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    let removed = inventory_remove_item_internal(ctx, ctx.sender, item_id, quantity)?;
+
+    // Drop in front of the player, or at their feet when that spot isn't walkable or visible
+    let (sin, cos) = entity.rotation.y.to_radians().sin_cos();
+    let x = entity.position.x + sin * DROP_DISTANCE;
+    let z = entity.position.z + cos * DROP_DISTANCE;
+    let position = match navmesh_surface_height(ctx, x, z) {
+        Some(y)
+            if is_position_valid(ctx, x, y, z)
+                && has_line_of_sight(ctx, &entity.position, &DbVector3 { x, y, z }, None) =>
+        {
+            DbVector3 { x, y, z }
+        }
+        _ => entity.position,
+    };
+
+    // Items with different durability are dropped separately
+    for item in removed {
+        let dropped = dropped_item_spawn(ctx, item, position.clone(), ctx.sender)?;
+        log::info!(
+            "Player {} dropped {} of item {} as entity {}",
+            ctx.sender,
+            dropped.item.quantity,
+            item_id,
+            dropped.entity_id
+        );
+    }
+    Ok(())
+}
+
+/// Pick up a dropped item, leaving behind whatever doesn't fit
+#[spacetimedb::reducer]
+pub fn item_pick_up(ctx: &ReducerContext, entity_id: u32) -> Result<(), String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    let player_entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+    let mut dropped = ctx
+        .db
+        .dropped_item()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Dropped item not found")?;
+    let item_entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Dropped item entity not found")?;
+
+    let config = dropped_item_get_config(ctx)?;
+    let distance = player_entity.position.distance(&item_entity.position);
+    if distance > config.pickup_range {
+        return Err(format!(
+            "Too far away to pick up. Distance: {:.1}, Range: {:.1}",
+            distance, config.pickup_range
+        ));
+    }
+    if !has_line_of_sight(ctx, &player_entity.position, &item_entity.position, None) {
+        return Err("Dropped item is not in line of sight".to_string());
+    }
+
+    zone_check_loot(ctx, &item_entity.position)?;
+    encumbrance_check_loot(ctx, &player)?;
+
+    let overflow = inventory_add_stack_partial(ctx, ctx.sender, &dropped.item)?;
+    if overflow == dropped.item.quantity {
+        return Err("Inventory full".to_string());
+    }
+
+    if overflow > 0 {
+        dropped.item.quantity = overflow;
+        ctx.db.dropped_item().entity_id().update(dropped);
+    } else {
+        dropped_item_delete(ctx, entity_id);
+    }
+    Ok(())
+}
+
+/// Remove dropped items that have been lying around too long
+#[spacetimedb::reducer]
+pub fn dropped_item_despawn_tick(
+    ctx: &ReducerContext,
+    _schedule: DroppedItemDespawnSchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err(
+            "Reducer `dropped_item_despawn_tick` may only be invoked by the scheduler".to_string(),
+        );
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let expired: Vec<_> = ctx
+        .db
+        .dropped_item()
+        .despawn_at_us()
+        .filter(..=current_time)
+        .map(|dropped| dropped.entity_id)
+        .collect();
+    for entity_id in expired {
+        dropped_item_delete(ctx, entity_id);
+    }
+    Ok(())
+}
+
+/// Set how long dropped items stay in the world (admin only)
+/// Applies to items dropped from now on
+#[spacetimedb::reducer]
+pub fn dropped_item_set_despawn_time(
+    ctx: &ReducerContext,
+    despawn_seconds: f32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if despawn_seconds <= 0.0 {
+        return Err("Despawn time must be positive".to_string());
+    }
+
+    let mut config = dropped_item_get_config(ctx)?;
+    config.despawn_after_us = (despawn_seconds * 1_000_000.0) as i64;
+    ctx.db.dropped_item_config().id().update(config);
+
+    log::info!("Dropped item despawn time set to {}s", despawn_seconds);
+    Ok(())
+}
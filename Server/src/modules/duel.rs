@@ -0,0 +1,421 @@
+use crate::modules::entity::{entity, DbEntityKind, Entity};
+use crate::modules::player::player;
+use crate::types::DbVector3;
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// How often duels are checked for timeouts and arena boundaries (500ms)
+const DUEL_TICK_INTERVAL_US: i64 = 500_000;
+/// Maximum distance between players to issue or accept a challenge
+const DUEL_CHALLENGE_RANGE: f32 = 10.0;
+/// Time a challenge stays open before expiring (30 seconds)
+const DUEL_CHALLENGE_TIMEOUT_US: i64 = 30_000_000;
+/// Allowed arena radius range
+const DUEL_MIN_ARENA_RADIUS: f32 = 5.0;
+const DUEL_MAX_ARENA_RADIUS: f32 = 50.0;
+/// Allowed time limit range in seconds
+const DUEL_MIN_TIME_LIMIT_SECS: f32 = 10.0;
+const DUEL_MAX_TIME_LIMIT_SECS: f32 = 600.0;
+/// Health a defeated duelist is left with
+pub const DUEL_DEFEAT_HEALTH: f32 = 1.0;
+
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbDuelStatus {
+    /// Waiting for the opponent to accept or decline
+    Pending,
+    /// Duelists can damage each other
+    Active,
+    /// Ended with a winner or in a draw
+    Finished,
+    /// Opponent declined the challenge
+    Declined,
+    /// Challenge was not answered in time
+    Expired,
+}
+
+/// How a finished duel ended
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbDuelOutcome {
+    /// Loser was brought down to 1 HP
+    Defeat,
+    /// Loser left the arena, went offline or gave up
+    Forfeit,
+    /// Time limit ran out (no winner)
+    TimeUp,
+}
+
+/// A duel challenge and, once accepted, the duel itself
+#[spacetimedb::table(name = duel, public)]
+pub struct Duel {
+    #[primary_key]
+    #[auto_inc]
+    pub duel_id: u32,
+    #[index(btree)]
+    pub status: DbDuelStatus,
+    #[index(btree)]
+    pub challenger: Identity,
+    /// References Entity.entity_id of the challenger
+    pub challenger_entity_id: u32,
+    #[index(btree)]
+    pub opponent: Identity,
+    /// References Entity.entity_id of the opponent
+    pub opponent_entity_id: u32,
+    /// Center of the arena, set when the duel starts
+    pub arena_center: DbVector3,
+    /// Duelists leaving this radius forfeit
+    pub arena_radius: f32,
+    pub time_limit_us: i64,
+    pub challenged_at_us: i64,
+    /// Timestamp when the duel was accepted (0 = not started)
+    pub started_at_us: i64,
+    /// Timestamp when the duel ended or the challenge was answered (0 = ongoing)
+    pub ended_at_us: i64,
+    pub outcome: Option<DbDuelOutcome>,
+    pub winner: Option<Identity>,
+}
+
+/// Schedule driving `duel_tick`
+#[spacetimedb::table(name = duel_schedule, scheduled(duel_tick))]
+pub struct DuelSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Start the duel schedule
+pub fn duel_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.duel_schedule().insert(DuelSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(DUEL_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+impl Duel {
+    fn involves(&self, entity_id: u32) -> bool {
+        self.challenger_entity_id == entity_id || self.opponent_entity_id == entity_id
+    }
+
+    fn is_between(&self, entity_a: u32, entity_b: u32) -> bool {
+        entity_a != entity_b && self.involves(entity_a) && self.involves(entity_b)
+    }
+
+    fn identity_of(&self, entity_id: u32) -> Identity {
+        if self.challenger_entity_id == entity_id {
+            self.challenger
+        } else {
+            self.opponent
+        }
+    }
+
+    fn other_identity(&self, identity: Identity) -> Identity {
+        if self.challenger == identity {
+            self.opponent
+        } else {
+            self.challenger
+        }
+    }
+}
+
+/// Find the pending or active duel a player is part of
+fn duel_find_open(ctx: &ReducerContext, identity: Identity) -> Option<Duel> {
+    let is_open = |duel: &Duel| matches!(duel.status, DbDuelStatus::Pending | DbDuelStatus::Active);
+    ctx.db
+        .duel()
+        .challenger()
+        .filter(identity)
+        .find(is_open)
+        .or_else(|| ctx.db.duel().opponent().filter(identity).find(is_open))
+}
+
+/// Find the active duel an entity is fighting in
+pub fn duel_find_active(ctx: &ReducerContext, entity_id: u32) -> Option<Duel> {
+    ctx.db
+        .duel()
+        .status()
+        .filter(&DbDuelStatus::Active)
+        .find(|duel| duel.involves(entity_id))
+}
+
+/// Check duel rules for a hit between two entities
+/// Returns the duel if the hit is between its duelists (bypassing faction and PvP rules),
+/// fails if a player in a duel is hit by or hits any other player
+pub fn duel_check_damage(
+    ctx: &ReducerContext,
+    attacker: &Entity,
+    target: &Entity,
+) -> Result<Option<Duel>, String> {
+    if attacker.kind != DbEntityKind::Player || target.kind != DbEntityKind::Player {
+        return Ok(None);
+    }
+
+    let attacker_duel = duel_find_active(ctx, attacker.entity_id);
+    let target_duel = duel_find_active(ctx, target.entity_id);
+
+    match (attacker_duel, target_duel) {
+        (Some(duel), Some(_)) if duel.is_between(attacker.entity_id, target.entity_id) => {
+            Ok(Some(duel))
+        }
+        (Some(_), _) => Err("You can only damage your duel opponent".to_string()),
+        (_, Some(_)) => Err("Target is in a duel".to_string()),
+        (None, None) => Ok(None),
+    }
+}
+
+/// End an active duel and record the result
+pub fn duel_finish(
+    ctx: &ReducerContext,
+    mut duel: Duel,
+    outcome: DbDuelOutcome,
+    winner: Option<Identity>,
+) {
+    duel.status = DbDuelStatus::Finished;
+    duel.outcome = Some(outcome);
+    duel.winner = winner;
+    duel.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+
+    log::info!(
+        "Duel {} finished ({:?}), winner: {:?}",
+        duel.duel_id,
+        outcome,
+        winner
+    );
+    ctx.db.duel().duel_id().update(duel);
+}
+
+/// Called when a duelist's health would drop to zero from their opponent's hit
+pub fn duel_on_defeated(ctx: &ReducerContext, duel: Duel, loser_entity_id: u32) {
+    let winner = duel.other_identity(duel.identity_of(loser_entity_id));
+    duel_finish(ctx, duel, DbDuelOutcome::Defeat, Some(winner));
+}
+
+/// Challenge a nearby player to a duel
+#[spacetimedb::reducer]
+pub fn duel_challenge(
+    ctx: &ReducerContext,
+    opponent: Identity,
+    arena_radius: f32,
+    time_limit_seconds: f32,
+) -> Result<(), String> {
+    if opponent == ctx.sender {
+        return Err("Cannot duel yourself".to_string());
+    }
+    if !(DUEL_MIN_ARENA_RADIUS..=DUEL_MAX_ARENA_RADIUS).contains(&arena_radius) {
+        return Err(format!(
+            "Arena radius must be between {} and {}",
+            DUEL_MIN_ARENA_RADIUS, DUEL_MAX_ARENA_RADIUS
+        ));
+    }
+    if !(DUEL_MIN_TIME_LIMIT_SECS..=DUEL_MAX_TIME_LIMIT_SECS).contains(&time_limit_seconds) {
+        return Err(format!(
+            "Time limit must be between {}s and {}s",
+            DUEL_MIN_TIME_LIMIT_SECS, DUEL_MAX_TIME_LIMIT_SECS
+        ));
+    }
+
+    let (challenger_entity, opponent_entity) = duel_get_duelists(ctx, ctx.sender, opponent)?;
+
+    if duel_find_open(ctx, ctx.sender).is_some() {
+        return Err("You already have an open duel".to_string());
+    }
+    if duel_find_open(ctx, opponent).is_some() {
+        return Err("Opponent already has an open duel".to_string());
+    }
+
+    let duel = ctx.db.duel().insert(Duel {
+        duel_id: 0,
+        status: DbDuelStatus::Pending,
+        challenger: ctx.sender,
+        challenger_entity_id: challenger_entity.entity_id,
+        opponent,
+        opponent_entity_id: opponent_entity.entity_id,
+        arena_center: DbVector3::default(),
+        arena_radius,
+        time_limit_us: (time_limit_seconds * 1_000_000.0) as i64,
+        challenged_at_us: ctx.timestamp.to_micros_since_unix_epoch(),
+        started_at_us: 0,
+        ended_at_us: 0,
+        outcome: None,
+        winner: None,
+    });
+
+    log::info!(
+        "Player {} challenged {} to duel {}",
+        ctx.sender,
+        opponent,
+        duel.duel_id
+    );
+    Ok(())
+}
+
+/// Accept a pending duel challenge, starting the duel
+#[spacetimedb::reducer]
+pub fn duel_accept(ctx: &ReducerContext, duel_id: u32) -> Result<(), String> {
+    let mut duel = duel_get_pending_challenge(ctx, duel_id)?;
+
+    let (challenger_entity, opponent_entity) =
+        duel_get_duelists(ctx, duel.challenger, duel.opponent)?;
+
+    duel.status = DbDuelStatus::Active;
+    duel.arena_center = challenger_entity
+        .position
+        .lerp(&opponent_entity.position, 0.5);
+    duel.started_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    ctx.db.duel().duel_id().update(duel);
+
+    log::info!("Duel {} started", duel_id);
+    Ok(())
+}
+
+/// Decline a pending duel challenge
+#[spacetimedb::reducer]
+pub fn duel_decline(ctx: &ReducerContext, duel_id: u32) -> Result<(), String> {
+    let mut duel = duel_get_pending_challenge(ctx, duel_id)?;
+
+    duel.status = DbDuelStatus::Declined;
+    duel.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    ctx.db.duel().duel_id().update(duel);
+
+    log::info!("Duel {} declined", duel_id);
+    Ok(())
+}
+
+/// Give up the current duel, handing the win to the opponent
+#[spacetimedb::reducer]
+pub fn duel_forfeit(ctx: &ReducerContext) -> Result<(), String> {
+    let duel = duel_find_open(ctx, ctx.sender)
+        .filter(|duel| duel.status == DbDuelStatus::Active)
+        .ok_or("You are not in a duel")?;
+
+    let winner = duel.other_identity(ctx.sender);
+    duel_finish(ctx, duel, DbDuelOutcome::Forfeit, Some(winner));
+    Ok(())
+}
+
+/// Find a pending challenge addressed to the caller that hasn't expired
+fn duel_get_pending_challenge(ctx: &ReducerContext, duel_id: u32) -> Result<Duel, String> {
+    let duel = ctx
+        .db
+        .duel()
+        .duel_id()
+        .find(duel_id)
+        .ok_or("Duel not found")?;
+
+    if duel.opponent != ctx.sender {
+        return Err("This challenge is not addressed to you".to_string());
+    }
+    if duel.status != DbDuelStatus::Pending
+        || ctx.timestamp.to_micros_since_unix_epoch() - duel.challenged_at_us
+            > DUEL_CHALLENGE_TIMEOUT_US
+    {
+        return Err("Challenge is no longer open".to_string());
+    }
+    Ok(duel)
+}
+
+/// Get both duelists' entities, checking that they are online, alive and close together
+fn duel_get_duelists(
+    ctx: &ReducerContext,
+    challenger: Identity,
+    opponent: Identity,
+) -> Result<(Entity, Entity), String> {
+    let mut entities = Vec::with_capacity(2);
+    for identity in [challenger, opponent] {
+        let player = ctx
+            .db
+            .player()
+            .identity()
+            .find(identity)
+            .ok_or("Player not found")?;
+        if !player.online {
+            return Err(format!("Player {} is not online", identity));
+        }
+
+        let entity = ctx
+            .db
+            .entity()
+            .entity_id()
+            .find(player.entity_id)
+            .ok_or("Player entity not found")?;
+        if entity.health <= 0.0 {
+            return Err(format!("Player {} is dead", identity));
+        }
+        entities.push(entity);
+    }
+
+    let opponent_entity = entities.pop().unwrap();
+    let challenger_entity = entities.pop().unwrap();
+
+    let distance = challenger_entity
+        .position
+        .distance(&opponent_entity.position);
+    if distance > DUEL_CHALLENGE_RANGE {
+        return Err(format!(
+            "Too far away to duel. Distance: {:.1}, Range: {:.1}",
+            distance, DUEL_CHALLENGE_RANGE
+        ));
+    }
+
+    Ok((challenger_entity, opponent_entity))
+}
+
+/// Expire unanswered challenges and end duels that ran out of time or lost a duelist
+#[spacetimedb::reducer]
+pub fn duel_tick(ctx: &ReducerContext, _schedule: DuelSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `duel_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+
+    let expired: Vec<_> = ctx
+        .db
+        .duel()
+        .status()
+        .filter(&DbDuelStatus::Pending)
+        .filter(|duel| current_time - duel.challenged_at_us > DUEL_CHALLENGE_TIMEOUT_US)
+        .collect();
+    for mut duel in expired {
+        duel.status = DbDuelStatus::Expired;
+        duel.ended_at_us = current_time;
+        ctx.db.duel().duel_id().update(duel);
+    }
+
+    let active: Vec<_> = ctx
+        .db
+        .duel()
+        .status()
+        .filter(&DbDuelStatus::Active)
+        .collect();
+    for duel in active {
+        if current_time - duel.started_at_us >= duel.time_limit_us {
+            duel_finish(ctx, duel, DbDuelOutcome::TimeUp, None);
+            continue;
+        }
+
+        // A duelist who went offline, died or left the arena forfeits
+        let forfeiter = [duel.challenger, duel.opponent]
+            .into_iter()
+            .find(|identity| {
+                let player = ctx.db.player().identity().find(*identity);
+                let entity = player
+                    .as_ref()
+                    .and_then(|player| ctx.db.entity().entity_id().find(player.entity_id));
+                match (player, entity) {
+                    (Some(player), Some(entity)) => {
+                        !player.online
+                            || entity.health <= 0.0
+                            || entity.position.distance(&duel.arena_center) > duel.arena_radius
+                    }
+                    _ => true,
+                }
+            });
+
+        if let Some(forfeiter) = forfeiter {
+            let winner = duel.other_identity(forfeiter);
+            duel_finish(ctx, duel, DbDuelOutcome::Forfeit, Some(winner));
+        }
+    }
+
+    Ok(())
+}
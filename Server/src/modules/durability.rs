@@ -0,0 +1,135 @@
+use crate::modules::equipment::{
+    equipment, equipment_apply_stats, equipment_get, DbEquipmentSlot, Equipment,
+};
+use crate::modules::inventory::{
+    inventory, inventory_find, inventory_remove_item_internal, ItemRef, ItemSlots,
+};
+use crate::modules::item_definition::item_definition_get;
+use spacetimedb::{Identity, ReducerContext};
+
+/// Durability an item loses each time it is used or hit
+const WEAR_PER_USE: u32 = 1;
+/// Slots whose items take wear when their wearer is hit
+const ARMOR_SLOTS: [DbEquipmentSlot; 5] = [
+    DbEquipmentSlot::OffHand,
+    DbEquipmentSlot::Head,
+    DbEquipmentSlot::Chest,
+    DbEquipmentSlot::Legs,
+    DbEquipmentSlot::Feet,
+];
+
+/// Wear down the items in some of a player's equipment slots
+/// Items that break stop providing stats, so the player's stats are re-derived
+fn durability_wear(ctx: &ReducerContext, mut equipment: Equipment, slots: &[DbEquipmentSlot]) {
+    let identity = equipment.identity;
+    let mut worn = false;
+    let mut broken = false;
+
+    for &slot in slots {
+        let Some(item) = equipment.slot_mut(slot) else {
+            continue;
+        };
+        let Some(durability) = item
+            .durability
+            .as_mut()
+            .filter(|durability| **durability > 0)
+        else {
+            continue;
+        };
+
+        *durability = durability.saturating_sub(WEAR_PER_USE);
+        worn = true;
+        if *durability == 0 {
+            broken = true;
+            log::info!(
+                "Item {} equipped by {} in {:?} broke",
+                item.id,
+                identity,
+                slot
+            );
+        }
+    }
+
+    if broken {
+        equipment_apply_stats(ctx, &equipment);
+    }
+    if worn {
+        ctx.db.equipment().identity().update(equipment);
+    }
+}
+
+/// Wear down the tool a player gathered with
+pub fn durability_on_gather(ctx: &ReducerContext, identity: Identity) {
+    if let Some(equipment) = ctx.db.equipment().identity().find(identity) {
+        durability_wear(ctx, equipment, &[DbEquipmentSlot::MainHand]);
+    }
+}
+
+/// Wear down the weapon of an attacking player
+pub fn durability_on_attack(ctx: &ReducerContext, attacker_entity_id: u32) {
+    if let Some(equipment) = ctx.db.equipment().entity_id().find(attacker_entity_id) {
+        durability_wear(ctx, equipment, &[DbEquipmentSlot::MainHand]);
+    }
+}
+
+/// Wear down the armor of a player that was hit
+pub fn durability_on_hit(ctx: &ReducerContext, target_entity_id: u32) {
+    if let Some(equipment) = ctx.db.equipment().entity_id().find(target_entity_id) {
+        durability_wear(ctx, equipment, &ARMOR_SLOTS);
+    }
+}
+
+/// Take an item's repair materials from the sender's inventory and restore it to full durability
+fn durability_repair(ctx: &ReducerContext, item: &mut ItemRef) -> Result<(), String> {
+    let definition = item_definition_get(ctx, item.id)?;
+    let durability = definition
+        .durability
+        .ok_or(format!("{} cannot be repaired", definition.name))?;
+    if item.durability.unwrap_or(0) >= durability.max_durability {
+        return Err(format!("{} is not damaged", definition.name));
+    }
+
+    for material in &durability.repair_materials {
+        inventory_remove_item_internal(ctx, ctx.sender, material.id, material.quantity)?;
+    }
+    item.durability = Some(durability.max_durability);
+
+    log::info!("Player {} repaired {}", ctx.sender, definition.name);
+    Ok(())
+}
+
+/// Repair the item in one of the sender's inventory slots
+#[spacetimedb::reducer]
+pub fn durability_repair_slot(ctx: &ReducerContext, slot: u32) -> Result<(), String> {
+    let mut item = inventory_find(ctx, ctx.sender)?.stack(slot)?.clone();
+    durability_repair(ctx, &mut item)?;
+
+    // Reload the inventory since the repair materials came out of it
+    let mut inventory = inventory_find(ctx, ctx.sender)?;
+    let index = inventory.slot_index(slot)?;
+    inventory.slots[index] = Some(item);
+    ctx.db.inventory().identity().update(inventory);
+    Ok(())
+}
+
+/// Repair the item in one of the sender's equipment slots
+#[spacetimedb::reducer]
+pub fn durability_repair_equipped(
+    ctx: &ReducerContext,
+    slot: DbEquipmentSlot,
+) -> Result<(), String> {
+    let mut equipment = equipment_get(ctx, ctx.sender)?;
+    let item = equipment
+        .slot_mut(slot)
+        .as_mut()
+        .ok_or(format!("Nothing is equipped in {:?}", slot))?;
+    let was_broken = item.is_broken();
+    durability_repair(ctx, item)?;
+
+    // A repaired item provides its stats again
+    if was_broken {
+        equipment_apply_stats(ctx, &equipment);
+    }
+    ctx.db.equipment().identity().update(equipment);
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::modules::admin::require_admin;
+use crate::modules::equipment::equipment_weight;
+use crate::modules::inventory::inventory_weight;
+use crate::modules::player::{player, Player};
+use spacetimedb::{Identity, ReducerContext, SpacetimeType};
+
+/// Carry capacity of new players (in item weight units)
+pub const DEFAULT_CARRY_CAPACITY: f32 = 100.0;
+
+/// How heavily a player is loaded relative to their carry capacity
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbEncumbranceTier {
+    /// Up to half of the carry capacity
+    Unencumbered,
+    /// Up to three quarters of the carry capacity
+    Burdened,
+    /// Up to the full carry capacity
+    Heavy,
+    /// Over the carry capacity (can't loot)
+    Overloaded,
+}
+
+impl DbEncumbranceTier {
+    /// Tier for a carried weight
+    pub fn from_load(weight: f32, capacity: f32) -> Self {
+        if weight > capacity {
+            return DbEncumbranceTier::Overloaded;
+        }
+        let load = if capacity > 0.0 {
+            weight / capacity
+        } else {
+            0.0
+        };
+        if load <= 0.5 {
+            DbEncumbranceTier::Unencumbered
+        } else if load <= 0.75 {
+            DbEncumbranceTier::Burdened
+        } else {
+            DbEncumbranceTier::Heavy
+        }
+    }
+
+    /// Multiplier applied to the walk and sprint speeds
+    pub fn speed_multiplier(self) -> f32 {
+        match self {
+            DbEncumbranceTier::Unencumbered => 1.0,
+            DbEncumbranceTier::Burdened => 0.9,
+            DbEncumbranceTier::Heavy => 0.7,
+            DbEncumbranceTier::Overloaded => 0.4,
+        }
+    }
+}
+
+/// Weight a player is carrying, counting both their inventory and their equipped items
+pub fn encumbrance_carried_weight(ctx: &ReducerContext, identity: Identity) -> f32 {
+    inventory_weight(ctx, identity) + equipment_weight(ctx, identity)
+}
+
+/// Encumbrance tier of a player from the weight they carry
+pub fn encumbrance_tier(ctx: &ReducerContext, player: &Player) -> DbEncumbranceTier {
+    DbEncumbranceTier::from_load(
+        encumbrance_carried_weight(ctx, player.identity),
+        player.carry_capacity,
+    )
+}
+
+/// Fail if a player is too heavily loaded to pick anything up
+pub fn encumbrance_check_loot(ctx: &ReducerContext, player: &Player) -> Result<(), String> {
+    let weight = encumbrance_carried_weight(ctx, player.identity);
+    if DbEncumbranceTier::from_load(weight, player.carry_capacity) == DbEncumbranceTier::Overloaded
+    {
+        return Err(format!(
+            "Carrying too much to loot ({:.1} / {:.1})",
+            weight, player.carry_capacity
+        ));
+    }
+    Ok(())
+}
+
+/// Set how much weight a player can carry (admin only)
+#[spacetimedb::reducer]
+pub fn encumbrance_set_carry_capacity(
+    ctx: &ReducerContext,
+    identity: Identity,
+    capacity: f32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if capacity < 0.0 {
+        return Err("Carry capacity cannot be negative".to_string());
+    }
+
+    let mut player = ctx
+        .db
+        .player()
+        .identity()
+        .find(identity)
+        .ok_or("Player not found")?;
+    player.carry_capacity = capacity;
+    ctx.db.player().identity().update(player);
+
+    log::info!("Carry capacity of {} set to {}", identity, capacity);
+    Ok(())
+}
@@ -1,7 +1,44 @@
-use crate::modules::player::player;
-use crate::modules::world_spawn::world_spawn;
+use crate::modules::boss::boss_on_damage;
+use crate::modules::building_piece_placed::{building_piece_destroy, building_piece_placed};
+use crate::modules::combat::{
+    combat_stagger, entity_defenses, is_staggered, mitigate_damage, resolve_defense,
+    DbDamageType, DbResistances, DefenseOutcome, HEAVY_ATTACK_DAMAGE_MULTIPLIER,
+};
+use crate::modules::duel::{duel_check_damage, duel_on_defeated, DUEL_DEFEAT_HEALTH};
+use crate::modules::durability::{durability_on_attack, durability_on_hit};
+use crate::modules::equipment::equipment_melee_damage_type;
+use crate::modules::faction::{faction_check_damage, UNAFFILIATED_FACTION_ID};
+use crate::modules::impulse::impulse_knockback;
+use crate::modules::line_of_sight::has_line_of_sight;
+use crate::modules::npc::{npc_on_damaged, npc_on_killed};
+use crate::modules::player::{player, player_respawn};
+use crate::modules::player_stats::{player_stats_on_damage, player_stats_on_kill};
+use crate::modules::pvp::pvp_check_damage;
+use crate::modules::stamina::{stamina_consume, ATTACK_STAMINA_COST, HEAVY_ATTACK_STAMINA_COST};
+use crate::modules::status_effect::{
+    status_effect_clear_entity, status_effect_damage_taken_multiplier,
+};
+use crate::modules::threat::threat_clear_source;
 use crate::types::DbVector3;
-use spacetimedb::{ReducerContext, Table};
+use spacetimedb::{ReducerContext, SpacetimeType, Table};
+
+/// What an entity represents in the world
+/// Kind-specific data lives in an extension table keyed by entity_id
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbEntityKind {
+    /// Extended by Player
+    Player,
+    /// Non-player character
+    Npc,
+    /// Wildlife and monsters
+    Creature,
+    /// Extended by DbBuildingPiecePlaced
+    Structure,
+    /// Extended by Projectile
+    Projectile,
+    /// Extended by DroppedItem
+    DroppedItem,
+}
 
 #[spacetimedb::table(name = entity, public)]
 pub struct Entity {
@@ -9,55 +46,144 @@ pub struct Entity {
     #[unique]
     #[auto_inc]
     pub entity_id: u32,
+    #[index(btree)]
+    pub kind: DbEntityKind,
+    /// References Faction.faction_id
+    pub faction_id: u32,
     pub position: DbVector3,
     pub rotation: DbVector3,
     pub health: f32,
     pub max_health: f32,
     pub attack_range: f32,
+    /// Most damage a single melee hit can deal
+    pub attack_damage: f32,
+    /// Reduces physical damage taken
+    pub armor: f32,
+    /// Fractional damage reduction per damage type
+    pub resistances: DbResistances,
+    /// Timestamp when the current block started (0 = not blocking)
+    pub block_started_at_us: i64,
+    /// Timestamp when the last block was lowered or broken (0 = never blocked)
+    pub block_ended_at_us: i64,
+    /// Timestamp of the most recent dodge (0 = never dodged)
+    pub dodge_started_at_us: i64,
+    /// Timestamp until which the entity is staggered and cannot act
+    pub staggered_until_us: i64,
+    /// Resource spent by sprinting, attacking and dodging
+    pub stamina: f32,
+    pub max_stamina: f32,
+    /// Stamina regained per second once regeneration resumes
+    pub stamina_regen_per_sec: f32,
+    /// Timestamp when stamina was last spent (regeneration is delayed after use)
+    pub last_stamina_use_us: i64,
 }
 
-pub fn entity_create(ctx: &ReducerContext) -> Result<Entity, String> {
-    let (position, rotation) = if let Some(spawn) = ctx.db.world_spawn().id().find(&0) {
-        (spawn.position, spawn.rotation)
-    } else {
-        (
-            DbVector3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
-            DbVector3 {
-                x: 0.0,
-                y: 0.0,
-                z: 0.0,
-            },
+impl DbEntityKind {
+    /// Whether entities of this kind have health and can be attacked
+    pub fn is_damageable(&self) -> bool {
+        matches!(
+            self,
+            DbEntityKind::Player
+                | DbEntityKind::Npc
+                | DbEntityKind::Creature
+                | DbEntityKind::Structure
         )
-    };
+    }
+}
 
+pub fn entity_create(
+    ctx: &ReducerContext,
+    kind: DbEntityKind,
+    position: DbVector3,
+    rotation: DbVector3,
+    max_health: f32,
+) -> Result<Entity, String> {
     let entity = ctx.db.entity().insert(Entity {
         entity_id: 0,
+        kind,
+        faction_id: UNAFFILIATED_FACTION_ID,
         position,
         rotation,
-        health: 100.0,
-        max_health: 100.0,
+        health: max_health,
+        max_health,
         attack_range: 3.0,
+        attack_damage: 10.0,
+        armor: 0.0,
+        resistances: DbResistances::default(),
+        block_started_at_us: 0,
+        block_ended_at_us: 0,
+        dodge_started_at_us: 0,
+        staggered_until_us: 0,
+        stamina: 100.0,
+        max_stamina: 100.0,
+        stamina_regen_per_sec: 20.0,
+        last_stamina_use_us: 0,
     });
 
-    log::debug!("Entity {} created", ctx.sender);
+    log::debug!("Entity {} ({:?}) created", entity.entity_id, kind);
 
     Ok(entity)
 }
 
+/// Remove an entity along with its status effects
+/// Callers are responsible for deleting the kind's extension row
+pub fn entity_delete(ctx: &ReducerContext, entity_id: u32) {
+    status_effect_clear_entity(ctx, entity_id);
+    ctx.db.entity().entity_id().delete(entity_id);
+}
+
+/// Find all entities within a radius of a point, optionally limited to one kind
+pub fn entity_find_in_radius(
+    ctx: &ReducerContext,
+    center: &DbVector3,
+    radius: f32,
+    kind: Option<DbEntityKind>,
+) -> Vec<Entity> {
+    let in_radius = |entity: &Entity| entity.position.distance(center) <= radius;
+
+    match kind {
+        Some(kind) => ctx.db.entity().kind().filter(&kind).filter(in_radius).collect(),
+        None => ctx.db.entity().iter().filter(in_radius).collect(),
+    }
+}
+
+/// Handle an entity's health reaching zero according to its kind
+/// `killer_entity_id` is 0 when nothing is to blame (entity ids start at 1)
+pub fn entity_on_killed(ctx: &ReducerContext, entity: &Entity, killer_entity_id: u32) {
+    log::info!(
+        "Entity {} ({:?}) was killed by entity {}",
+        entity.entity_id,
+        entity.kind,
+        killer_entity_id
+    );
+
+    // Dead entities are no longer worth attacking
+    threat_clear_source(ctx, entity.entity_id);
+    player_stats_on_kill(ctx, killer_entity_id, entity);
+
+    match entity.kind {
+        DbEntityKind::Player => player_respawn(ctx, entity.entity_id),
+        DbEntityKind::Npc | DbEntityKind::Creature => {
+            npc_on_killed(ctx, entity.entity_id, killer_entity_id)
+        }
+        DbEntityKind::Structure => building_piece_destroy(ctx, entity.entity_id),
+        DbEntityKind::Projectile | DbEntityKind::DroppedItem => {}
+    }
+}
+
 /// Apply damage from one entity to another
-/// Validates that the attacker is online and in range
+/// Validates that the attacker is online, in range and has line of sight
+/// Heavy attacks cost more stamina and knock the target back
+/// The damage type comes from the attacker's main hand weapon
 #[spacetimedb::reducer]
 pub fn entity_apply_damage(
     ctx: &ReducerContext,
     target_entity_id: u32,
     damage: f32,
+    heavy: bool,
 ) -> Result<(), String> {
     // Get attacker's player to verify they're online
-    let attacker = ctx.db.player().identity().find(&ctx.sender)
+    let attacker = ctx.db.player().identity().find(ctx.sender)
         .ok_or("Attacker not found")?;
 
     if !attacker.online {
@@ -65,11 +191,15 @@ pub fn entity_apply_damage(
     }
 
     // Get attacker's entity for position and attack range
-    let attacker_entity = ctx.db.entity().entity_id().find(&attacker.entity_id)
+    let attacker_entity = ctx.db.entity().entity_id().find(attacker.entity_id)
         .ok_or("Attacker entity not found")?;
 
+    if is_staggered(&attacker_entity, ctx.timestamp.to_micros_since_unix_epoch()) {
+        return Err("Attacker is staggered".to_string());
+    }
+
     // Get target entity
-    let mut target_entity = ctx.db.entity().entity_id().find(&target_entity_id)
+    let target_entity = ctx.db.entity().entity_id().find(target_entity_id)
         .ok_or("Target entity not found")?;
 
     // Check if attacker is in range of target
@@ -82,25 +212,174 @@ pub fn entity_apply_damage(
         ));
     }
 
+    // Check that no building piece or terrain blocks the attack (a targeted piece can't block itself)
+    let target_piece_id = ctx
+        .db
+        .building_piece_placed()
+        .entity_id()
+        .find(target_entity_id)
+        .map(|piece| piece.piece_id);
+    if !has_line_of_sight(
+        ctx,
+        &attacker_entity.position,
+        &target_entity.position,
+        target_piece_id,
+    ) {
+        return Err("Target is not in line of sight".to_string());
+    }
+
+    // Attacking costs stamina
+    let stamina_cost = if heavy {
+        HEAVY_ATTACK_STAMINA_COST
+    } else {
+        ATTACK_STAMINA_COST
+    };
+    stamina_consume(ctx, attacker.entity_id, stamina_cost)?;
+
+    // Clients can't hit harder than the attacker's damage stat allows
+    let max_damage = if heavy {
+        attacker_entity.attack_damage * HEAVY_ATTACK_DAMAGE_MULTIPLIER
+    } else {
+        attacker_entity.attack_damage
+    };
+    let damage = damage.min(max_damage);
+    // The attacker's weapon decides the damage type, not the client
+    let damage_type = equipment_melee_damage_type(ctx, attacker.entity_id);
+
+    let damage_dealt = entity_apply_damage_internal(
+        ctx,
+        attacker.entity_id,
+        target_entity_id,
+        damage,
+        damage_type,
+    )?;
+    durability_on_attack(ctx, attacker.entity_id);
+
+    // Heavy hits that land push the target away (structures and the dead stay put)
+    let target_alive = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(target_entity_id)
+        .is_some_and(|target| target.health > 0.0);
+    if heavy && damage_dealt > 0.0 && target_alive {
+        if let Err(err) = impulse_knockback(ctx, target_entity_id, &attacker_entity.position) {
+            log::debug!("No knockback on entity {}: {}", target_entity_id, err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Internal function for applying damage (used by server-side logic like projectiles)
+/// Resolves blocks and dodges, mitigates damage by the target's defenses and status effects,
+/// and returns the damage dealt
+pub fn entity_apply_damage_internal(
+    ctx: &ReducerContext,
+    attacker_entity_id: u32,
+    target_entity_id: u32,
+    damage: f32,
+    damage_type: DbDamageType,
+) -> Result<f32, String> {
+    let mut target_entity = ctx.db.entity().entity_id().find(target_entity_id)
+        .ok_or("Target entity not found")?;
+    let attacker_entity = ctx.db.entity().entity_id().find(attacker_entity_id);
+
+    if !target_entity.kind.is_damageable() {
+        return Err(format!("{:?} entities cannot be damaged", target_entity.kind));
+    }
+    if target_entity.health <= 0.0 {
+        return Err("Target is already dead".to_string());
+    }
+
+    // Duelists may only hurt each other, and their hits ignore faction and PvP rules
+    let duel = match &attacker_entity {
+        Some(attacker) => duel_check_damage(ctx, attacker, &target_entity)?,
+        None => None,
+    };
+
+    // Otherwise factions and PvP rules decide who may hurt whom
+    if let (Some(attacker), None) = (&attacker_entity, &duel) {
+        faction_check_damage(ctx, attacker, &target_entity)?;
+        pvp_check_damage(ctx, attacker, &target_entity)?;
+    }
+
+    // Check the target's block or dodge against where the hit comes from
+    let attacker_position = attacker_entity
+        .as_ref()
+        .map_or(target_entity.position.clone(), |attacker| attacker.position.clone());
+    let outcome = resolve_defense(
+        &target_entity,
+        &attacker_position,
+        ctx.timestamp.to_micros_since_unix_epoch(),
+    );
+
+    match outcome {
+        DefenseOutcome::Dodged => {
+            log::info!("Entity {} dodged entity {}", target_entity_id, attacker_entity_id);
+            return Ok(0.0);
+        }
+        DefenseOutcome::Parried => {
+            // Only attackers close enough to be deflected are staggered
+            if let Some(attacker) = attacker_entity.filter(|attacker| {
+                attacker.position.distance(&target_entity.position) <= attacker.attack_range
+            }) {
+                combat_stagger(ctx, attacker.entity_id);
+            }
+            log::info!("Entity {} parried entity {}", target_entity_id, attacker_entity_id);
+            return Ok(0.0);
+        }
+        DefenseOutcome::Blocked | DefenseOutcome::Hit => {}
+    }
+    let damage = damage * outcome.damage_multiplier();
+
+    // Reduce damage by the target's armor and resistances
+    let (armor, resistances) = entity_defenses(ctx, &target_entity);
+    let damage = mitigate_damage(damage, damage_type, armor, &resistances);
+
+    // Scale damage by the target's active status effects
+    let damage = damage * status_effect_damage_taken_multiplier(ctx, target_entity_id);
+
     // Apply damage
+    let health_before = target_entity.health;
     target_entity.health -= damage;
     if target_entity.health < 0.0 {
         target_entity.health = 0.0;
     }
-    ctx.db.entity().entity_id().update(target_entity);
+
+    // Duels end at 1 HP instead of death
+    let duel_defeat = duel.filter(|_| target_entity.health < DUEL_DEFEAT_HEALTH);
+    if duel_defeat.is_some() {
+        target_entity.health = DUEL_DEFEAT_HEALTH.min(health_before);
+    }
+    let target_entity = ctx.db.entity().entity_id().update(target_entity);
 
     log::info!(
-        "Entity {} dealt {:.1} damage to entity {} (distance: {:.1})",
-        attacker.entity_id, damage, target_entity_id, distance
+        "Entity {} dealt {:.1} {:?} damage to entity {}",
+        attacker_entity_id, damage, damage_type, target_entity_id
     );
 
-    Ok(())
+    if damage > 0.0 {
+        boss_on_damage(ctx, attacker_entity_id, target_entity_id, damage);
+        player_stats_on_damage(ctx, attacker_entity_id, target_entity_id, damage);
+        durability_on_hit(ctx, target_entity_id);
+    }
+
+    if let Some(duel) = duel_defeat {
+        duel_on_defeated(ctx, duel, target_entity_id);
+    } else if target_entity.health <= 0.0 {
+        entity_on_killed(ctx, &target_entity, attacker_entity_id);
+    } else if matches!(target_entity.kind, DbEntityKind::Npc | DbEntityKind::Creature) {
+        npc_on_damaged(ctx, target_entity_id, attacker_entity_id, damage);
+    }
+
+    Ok(damage)
 }
 
 /// Reset an entity's health to max (admin functionality)
 #[spacetimedb::reducer]
 pub fn entity_reset_health(ctx: &ReducerContext, entity_id: u32) -> Result<(), String> {
-    let mut entity = ctx.db.entity().entity_id().find(&entity_id)
+    let mut entity = ctx.db.entity().entity_id().find(entity_id)
         .ok_or("Entity not found")?;
 
     let max_health = entity.max_health;
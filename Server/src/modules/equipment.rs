@@ -0,0 +1,443 @@
+use crate::modules::admin::require_admin;
+use crate::modules::combat::{DbDamageType, DbResistances};
+use crate::modules::entity::entity;
+use crate::modules::inventory::{
+    inventory_add_stack_internal, inventory_remove_item_internal, item_stacks_weight, ItemRef,
+};
+use crate::modules::item_definition::{item_definition, item_definition_get};
+use crate::modules::player::player;
+use spacetimedb::{Identity, ReducerContext, SpacetimeType, Table};
+
+/// Sprint speed as a multiple of the walk speed
+const SPRINT_SPEED_RATIO: f32 = 1.5;
+/// Damage type of melee hits without a (working) weapon
+const UNARMED_DAMAGE_TYPE: DbDamageType = DbDamageType::Blunt;
+
+/// Where an item is worn or held
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbEquipmentSlot {
+    MainHand,
+    OffHand,
+    Head,
+    Chest,
+    Legs,
+    Feet,
+    Trinket,
+}
+
+/// Stats an entity's combat and movement are derived from
+#[derive(SpacetimeType, Clone, Debug, Default, PartialEq)]
+pub struct DbStats {
+    pub attack_range: f32,
+    /// Most damage a single melee hit can deal
+    pub attack_damage: f32,
+    /// Reduces physical damage taken
+    pub armor: f32,
+    pub max_health: f32,
+    /// Walk speed in units per second (sprinting is proportionally faster)
+    pub movement_speed: f32,
+    pub resistances: DbResistances,
+}
+
+impl DbStats {
+    pub fn add(&self, other: &DbStats) -> DbStats {
+        DbStats {
+            attack_range: self.attack_range + other.attack_range,
+            attack_damage: self.attack_damage + other.attack_damage,
+            armor: self.armor + other.armor,
+            max_health: self.max_health + other.max_health,
+            movement_speed: self.movement_speed + other.movement_speed,
+            resistances: self.resistances.add(&other.resistances),
+        }
+    }
+}
+
+/// How an item can be equipped
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbEquippable {
+    pub slot: DbEquipmentSlot,
+    /// Added to the wearer's base stats while equipped
+    pub bonuses: DbStats,
+    /// Damage type of melee hits made with this item (main hand weapons only)
+    pub damage_type: Option<DbDamageType>,
+}
+
+/// Items a player has equipped (one item per slot)
+#[spacetimedb::table(name = equipment, public)]
+pub struct Equipment {
+    #[primary_key]
+    pub identity: Identity,
+    /// References Entity.entity_id of the player
+    #[unique]
+    pub entity_id: u32,
+    /// Stats without any equipment
+    pub base_stats: DbStats,
+    pub main_hand: Option<ItemRef>,
+    pub off_hand: Option<ItemRef>,
+    pub head: Option<ItemRef>,
+    pub chest: Option<ItemRef>,
+    pub legs: Option<ItemRef>,
+    pub feet: Option<ItemRef>,
+    pub trinket: Option<ItemRef>,
+}
+
+impl Equipment {
+    pub fn slot_mut(&mut self, slot: DbEquipmentSlot) -> &mut Option<ItemRef> {
+        match slot {
+            DbEquipmentSlot::MainHand => &mut self.main_hand,
+            DbEquipmentSlot::OffHand => &mut self.off_hand,
+            DbEquipmentSlot::Head => &mut self.head,
+            DbEquipmentSlot::Chest => &mut self.chest,
+            DbEquipmentSlot::Legs => &mut self.legs,
+            DbEquipmentSlot::Feet => &mut self.feet,
+            DbEquipmentSlot::Trinket => &mut self.trinket,
+        }
+    }
+
+    /// Every equipped item
+    pub fn equipped(&self) -> impl Iterator<Item = &ItemRef> {
+        [
+            &self.main_hand,
+            &self.off_hand,
+            &self.head,
+            &self.chest,
+            &self.legs,
+            &self.feet,
+            &self.trinket,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Create empty equipment for the sender, taking their current stats as the base
+pub fn equipment_create(ctx: &ReducerContext) -> Result<(), String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    ctx.db.equipment().insert(Equipment {
+        identity: ctx.sender,
+        entity_id: entity.entity_id,
+        base_stats: DbStats {
+            attack_range: entity.attack_range,
+            attack_damage: entity.attack_damage,
+            armor: entity.armor,
+            max_health: entity.max_health,
+            movement_speed: player.movement_speed,
+            resistances: entity.resistances.clone(),
+        },
+        main_hand: None,
+        off_hand: None,
+        head: None,
+        chest: None,
+        legs: None,
+        feet: None,
+        trinket: None,
+    });
+    Ok(())
+}
+
+pub fn equipment_get(ctx: &ReducerContext, identity: Identity) -> Result<Equipment, String> {
+    ctx.db
+        .equipment()
+        .identity()
+        .find(identity)
+        .ok_or("Equipment not found".to_string())
+}
+
+/// Total weight of a player's equipped items (0 without equipment)
+pub fn equipment_weight(ctx: &ReducerContext, identity: Identity) -> f32 {
+    ctx.db
+        .equipment()
+        .identity()
+        .find(identity)
+        .map_or(0.0, |equipment| {
+            item_stacks_weight(ctx, equipment.equipped())
+        })
+}
+
+/// Base stats plus the bonuses of every equipped item that isn't broken
+pub fn equipment_total_stats(ctx: &ReducerContext, equipment: &Equipment) -> DbStats {
+    equipment_sum_stats(equipment, |item_id| {
+        ctx.db.item_definition().id().find(item_id)?.equipment
+    })
+}
+
+/// Add the bonuses of every unbroken equipped item to the base stats
+fn equipment_sum_stats(
+    equipment: &Equipment,
+    equippable: impl Fn(u32) -> Option<DbEquippable>,
+) -> DbStats {
+    equipment
+        .equipped()
+        .filter(|item| !item.is_broken())
+        .filter_map(|item| equippable(item.id))
+        .fold(equipment.base_stats.clone(), |stats, equippable| {
+            stats.add(&equippable.bonuses)
+        })
+}
+
+/// Damage type of an entity's melee hits, decided by the weapon in their main hand
+/// Entities without equipment (or without a working weapon) hit unarmed
+pub fn equipment_melee_damage_type(ctx: &ReducerContext, entity_id: u32) -> DbDamageType {
+    ctx.db
+        .equipment()
+        .entity_id()
+        .find(entity_id)
+        .map_or(UNARMED_DAMAGE_TYPE, |equipment| {
+            equipment_weapon_damage_type(&equipment, |item_id| {
+                ctx.db.item_definition().id().find(item_id)?.equipment
+            })
+        })
+}
+
+fn equipment_weapon_damage_type(
+    equipment: &Equipment,
+    equippable: impl Fn(u32) -> Option<DbEquippable>,
+) -> DbDamageType {
+    equipment
+        .main_hand
+        .as_ref()
+        .filter(|item| !item.is_broken())
+        .and_then(|item| equippable(item.id)?.damage_type)
+        .unwrap_or(UNARMED_DAMAGE_TYPE)
+}
+
+/// Write a player's derived stats to their entity and player rows
+pub fn equipment_apply_stats(ctx: &ReducerContext, equipment: &Equipment) {
+    let stats = equipment_total_stats(ctx, equipment);
+
+    if let Some(mut entity) = ctx.db.entity().entity_id().find(equipment.entity_id) {
+        entity.attack_range = stats.attack_range.max(0.0);
+        entity.attack_damage = stats.attack_damage.max(0.0);
+        entity.armor = stats.armor;
+        entity.resistances = stats.resistances;
+        entity.max_health = stats.max_health.max(1.0);
+        entity.health = entity.health.min(entity.max_health);
+        ctx.db.entity().entity_id().update(entity);
+    }
+    if let Some(mut player) = ctx.db.player().identity().find(equipment.identity) {
+        player.movement_speed = stats.movement_speed.max(0.0);
+        player.sprint_speed = player.movement_speed * SPRINT_SPEED_RATIO;
+        ctx.db.player().identity().update(player);
+    }
+}
+
+/// Equip an item from the inventory, returning whatever was in its slot
+#[spacetimedb::reducer]
+pub fn equipment_equip(ctx: &ReducerContext, item_id: u32) -> Result<(), String> {
+    let definition = item_definition_get(ctx, item_id)?;
+    let equippable = definition
+        .equipment
+        .ok_or(format!("{} cannot be equipped", definition.name))?;
+    let mut equipment = equipment_get(ctx, ctx.sender)?;
+
+    let item = inventory_remove_item_internal(ctx, ctx.sender, item_id, 1)?
+        .pop()
+        .ok_or("Item not found in inventory")?;
+    let previous = equipment.slot_mut(equippable.slot).replace(item);
+    if let Some(previous) = previous {
+        inventory_add_stack_internal(ctx, ctx.sender, &previous)?;
+    }
+
+    equipment_apply_stats(ctx, &equipment);
+    ctx.db.equipment().identity().update(equipment);
+    Ok(())
+}
+
+/// Move the item in an equipment slot back into the inventory
+#[spacetimedb::reducer]
+pub fn equipment_unequip(ctx: &ReducerContext, slot: DbEquipmentSlot) -> Result<(), String> {
+    let mut equipment = equipment_get(ctx, ctx.sender)?;
+    let item = equipment
+        .slot_mut(slot)
+        .take()
+        .ok_or(format!("Nothing is equipped in {:?}", slot))?;
+
+    inventory_add_stack_internal(ctx, ctx.sender, &item)?;
+
+    equipment_apply_stats(ctx, &equipment);
+    ctx.db.equipment().identity().update(equipment);
+    Ok(())
+}
+
+/// Replace a player's stats without equipment (admin only)
+#[spacetimedb::reducer]
+pub fn equipment_set_base_stats(
+    ctx: &ReducerContext,
+    identity: Identity,
+    base_stats: DbStats,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let mut equipment = equipment_get(ctx, identity)?;
+    equipment.base_stats = base_stats;
+    equipment_apply_stats(ctx, &equipment);
+    ctx.db.equipment().identity().update(equipment);
+
+    log::info!("Updated base stats of {}", identity);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWORD: u32 = 1;
+    const HELMET: u32 = 2;
+
+    fn stats(attack_damage: f32, armor: f32) -> DbStats {
+        DbStats {
+            attack_range: 2.0,
+            attack_damage,
+            armor,
+            max_health: 100.0,
+            movement_speed: 4.0,
+            resistances: DbResistances {
+                fire: 0.1,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn equippable(item_id: u32) -> Option<DbEquippable> {
+        match item_id {
+            SWORD => Some(DbEquippable {
+                slot: DbEquipmentSlot::MainHand,
+                bonuses: DbStats {
+                    attack_damage: 15.0,
+                    ..Default::default()
+                },
+                damage_type: Some(DbDamageType::Slash),
+            }),
+            HELMET => Some(DbEquippable {
+                slot: DbEquipmentSlot::Head,
+                bonuses: DbStats {
+                    armor: 10.0,
+                    movement_speed: -0.5,
+                    resistances: DbResistances {
+                        slash: 0.2,
+                        fire: -0.05,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                damage_type: None,
+            }),
+            _ => None,
+        }
+    }
+
+    fn equipment(main_hand: Option<ItemRef>, head: Option<ItemRef>) -> Equipment {
+        Equipment {
+            identity: Identity::ZERO,
+            entity_id: 1,
+            base_stats: stats(10.0, 0.0),
+            main_hand,
+            off_hand: None,
+            head,
+            chest: None,
+            legs: None,
+            feet: None,
+            trinket: None,
+        }
+    }
+
+    #[test]
+    fn stats_add_every_field() {
+        let total = stats(10.0, 5.0).add(&stats(3.0, -2.0));
+        assert_eq!(
+            total,
+            DbStats {
+                attack_range: 4.0,
+                attack_damage: 13.0,
+                armor: 3.0,
+                max_health: 200.0,
+                movement_speed: 8.0,
+                resistances: DbResistances {
+                    fire: 0.2,
+                    ..Default::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn no_equipment_keeps_base_stats() {
+        let equipment = equipment(None, None);
+        assert_eq!(
+            equipment_sum_stats(&equipment, equippable),
+            stats(10.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn equipped_bonuses_are_summed() {
+        let equipment = equipment(
+            Some(ItemRef::with_durability(SWORD, 50)),
+            Some(ItemRef::new(HELMET, 1)),
+        );
+        let total = equipment_sum_stats(&equipment, equippable);
+
+        assert_eq!(total.attack_damage, 25.0);
+        assert_eq!(total.armor, 10.0);
+        assert_eq!(total.movement_speed, 3.5);
+        assert_eq!(total.resistances.slash, 0.2);
+        assert!((total.resistances.fire - 0.05).abs() < 1e-6);
+    }
+
+    #[test]
+    fn broken_items_provide_no_bonuses() {
+        let equipment = equipment(
+            Some(ItemRef::with_durability(SWORD, 0)),
+            Some(ItemRef::with_durability(HELMET, 1)),
+        );
+        let total = equipment_sum_stats(&equipment, equippable);
+
+        assert_eq!(total.attack_damage, 10.0);
+        assert_eq!(total.armor, 10.0);
+    }
+
+    #[test]
+    fn items_that_are_not_equippable_are_ignored() {
+        let equipment = equipment(Some(ItemRef::new(99, 1)), None);
+        assert_eq!(
+            equipment_sum_stats(&equipment, equippable),
+            stats(10.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn weapon_decides_melee_damage_type() {
+        let equipment = equipment(Some(ItemRef::with_durability(SWORD, 50)), None);
+        assert_eq!(
+            equipment_weapon_damage_type(&equipment, equippable),
+            DbDamageType::Slash
+        );
+    }
+
+    #[test]
+    fn unarmed_or_broken_weapon_hits_blunt() {
+        for main_hand in [
+            None,
+            Some(ItemRef::with_durability(SWORD, 0)),
+            Some(ItemRef::new(HELMET, 1)),
+        ] {
+            let equipment = equipment(main_hand, None);
+            assert_eq!(
+                equipment_weapon_damage_type(&equipment, equippable),
+                UNARMED_DAMAGE_TYPE
+            );
+        }
+    }
+}
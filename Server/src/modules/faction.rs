@@ -0,0 +1,208 @@
+use crate::modules::admin::require_admin;
+use crate::modules::entity::{entity, Entity};
+use spacetimedb::{ReducerContext, SpacetimeType, Table};
+
+/// Faction of entities that don't belong to any faction (players by default, structures)
+pub const UNAFFILIATED_FACTION_ID: u32 = 0;
+
+/// How two factions treat each other
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbFactionRelationship {
+    /// Attack on sight and can be damaged
+    Hostile,
+    /// Left alone unless provoked, but can be damaged
+    Neutral,
+    /// Cannot damage each other
+    Allied,
+}
+
+/// A group of players and NPCs that share relationships (e.g., "Bandits", "Wildlife")
+#[spacetimedb::table(name = faction, public)]
+pub struct Faction {
+    #[primary_key]
+    pub faction_id: u32,
+    pub name: String,
+}
+
+/// Relationship between two different factions
+/// Stored once per pair with faction_a < faction_b; missing pairs are Neutral
+#[spacetimedb::table(name = faction_relationship, public)]
+pub struct FactionRelationship {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    #[index(btree)]
+    pub faction_a: u32,
+    pub faction_b: u32,
+    pub relationship: DbFactionRelationship,
+}
+
+/// Initialize default factions and their relationships
+pub fn faction_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Unaffiliated - faction_id 0
+    ctx.db.faction().insert(Faction {
+        faction_id: UNAFFILIATED_FACTION_ID,
+        name: "Unaffiliated".to_string(),
+    });
+
+    // Settlers - faction_id 1
+    ctx.db.faction().insert(Faction {
+        faction_id: 1,
+        name: "Settlers".to_string(),
+    });
+
+    // Bandits - faction_id 2
+    ctx.db.faction().insert(Faction {
+        faction_id: 2,
+        name: "Bandits".to_string(),
+    });
+
+    // Wildlife - faction_id 3
+    ctx.db.faction().insert(Faction {
+        faction_id: 3,
+        name: "Wildlife".to_string(),
+    });
+
+    // Bandits and wildlife attack anyone who isn't one of them
+    faction_relationship_set(ctx, 0, 2, DbFactionRelationship::Hostile);
+    faction_relationship_set(ctx, 1, 2, DbFactionRelationship::Hostile);
+    faction_relationship_set(ctx, 0, 3, DbFactionRelationship::Hostile);
+    faction_relationship_set(ctx, 1, 3, DbFactionRelationship::Hostile);
+
+    log::info!("Initialized default factions");
+    Ok(())
+}
+
+fn faction_relationship_find(
+    ctx: &ReducerContext,
+    faction_a: u32,
+    faction_b: u32,
+) -> Option<FactionRelationship> {
+    let (faction_a, faction_b) = (faction_a.min(faction_b), faction_a.max(faction_b));
+    ctx.db
+        .faction_relationship()
+        .faction_a()
+        .filter(faction_a)
+        .find(|row| row.faction_b == faction_b)
+}
+
+fn faction_relationship_set(
+    ctx: &ReducerContext,
+    faction_a: u32,
+    faction_b: u32,
+    relationship: DbFactionRelationship,
+) {
+    match faction_relationship_find(ctx, faction_a, faction_b) {
+        Some(mut row) => {
+            row.relationship = relationship;
+            ctx.db.faction_relationship().id().update(row);
+        }
+        None => {
+            ctx.db.faction_relationship().insert(FactionRelationship {
+                id: 0,
+                faction_a: faction_a.min(faction_b),
+                faction_b: faction_a.max(faction_b),
+                relationship,
+            });
+        }
+    }
+}
+
+/// Look up how two factions treat each other
+/// Members of the same faction are allied, except unaffiliated entities
+pub fn faction_relationship_between(
+    ctx: &ReducerContext,
+    faction_a: u32,
+    faction_b: u32,
+) -> DbFactionRelationship {
+    if faction_a == faction_b && faction_a != UNAFFILIATED_FACTION_ID {
+        return DbFactionRelationship::Allied;
+    }
+
+    faction_relationship_find(ctx, faction_a, faction_b)
+        .map_or(DbFactionRelationship::Neutral, |row| row.relationship)
+}
+
+/// Check that the attacker's faction allows it to damage the target
+pub fn faction_check_damage(
+    ctx: &ReducerContext,
+    attacker: &Entity,
+    target: &Entity,
+) -> Result<(), String> {
+    if attacker.entity_id != target.entity_id
+        && faction_relationship_between(ctx, attacker.faction_id, target.faction_id)
+            == DbFactionRelationship::Allied
+    {
+        return Err("Cannot damage an allied entity".to_string());
+    }
+    Ok(())
+}
+
+/// Creates a new faction (admin only)
+#[spacetimedb::reducer]
+pub fn faction_create(ctx: &ReducerContext, faction_id: u32, name: String) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx.db.faction().faction_id().find(faction_id).is_some() {
+        return Err(format!("Faction {} already exists", faction_id));
+    }
+
+    ctx.db.faction().insert(Faction { faction_id, name });
+    log::info!("Created faction with faction_id: {}", faction_id);
+    Ok(())
+}
+
+/// Set how two factions treat each other (admin only)
+#[spacetimedb::reducer]
+pub fn faction_set_relationship(
+    ctx: &ReducerContext,
+    faction_a: u32,
+    faction_b: u32,
+    relationship: DbFactionRelationship,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if faction_a == faction_b {
+        return Err("A faction's relationship with itself cannot be changed".to_string());
+    }
+    for faction_id in [faction_a, faction_b] {
+        if ctx.db.faction().faction_id().find(faction_id).is_none() {
+            return Err(format!("Faction {} does not exist", faction_id));
+        }
+    }
+
+    faction_relationship_set(ctx, faction_a, faction_b, relationship);
+    log::info!(
+        "Factions {} and {} are now {:?}",
+        faction_a,
+        faction_b,
+        relationship
+    );
+    Ok(())
+}
+
+/// Move an entity (player or NPC) into a faction (admin only)
+#[spacetimedb::reducer]
+pub fn faction_set_entity_faction(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    faction_id: u32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx.db.faction().faction_id().find(faction_id).is_none() {
+        return Err(format!("Faction {} does not exist", faction_id));
+    }
+
+    let mut entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Entity not found")?;
+    entity.faction_id = faction_id;
+    ctx.db.entity().entity_id().update(entity);
+
+    log::info!("Entity {} joined faction {}", entity_id, faction_id);
+    Ok(())
+}
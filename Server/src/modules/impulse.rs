@@ -0,0 +1,208 @@
+use crate::modules::entity::{entity, DbEntityKind};
+use crate::modules::line_of_sight::segment_hits_building_piece;
+use crate::modules::navmesh::{is_position_valid, navmesh_surface_height};
+use crate::modules::player::player;
+use crate::types::DbVector3;
+use spacetimedb::{ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often impulses move their entities (50ms)
+const IMPULSE_TICK_INTERVAL_US: i64 = 50_000;
+/// Height above an entity's feet at which pushes collide with building pieces
+/// (high enough to pass over the floor the entity is standing on)
+const IMPULSE_COLLISION_HEIGHT: f32 = 1.0;
+/// Initial speed of a heavy attack's knockback in units per second
+pub const KNOCKBACK_SPEED: f32 = 8.0;
+/// How long a heavy attack's knockback lasts (250ms)
+pub const KNOCKBACK_DURATION_US: i64 = 250_000;
+
+/// Server-driven movement that overrides client movement until it ends
+#[spacetimedb::table(name = entity_impulse, public)]
+pub struct EntityImpulse {
+    /// References Entity.entity_id
+    #[primary_key]
+    pub entity_id: u32,
+    /// Horizontal velocity at the start of the impulse (slows linearly to zero)
+    pub velocity: DbVector3,
+    pub started_at_us: i64,
+    pub ends_at_us: i64,
+    /// Timestamp of the last movement step
+    pub last_tick_us: i64,
+}
+
+/// Schedule driving `impulse_tick`
+#[spacetimedb::table(name = impulse_schedule, scheduled(impulse_tick))]
+pub struct ImpulseSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Start the impulse schedule
+pub fn impulse_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.impulse_schedule().insert(ImpulseSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(IMPULSE_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+/// Whether server-driven movement currently controls an entity
+pub fn impulse_is_active(ctx: &ReducerContext, entity_id: u32) -> bool {
+    ctx.db
+        .entity_impulse()
+        .entity_id()
+        .find(entity_id)
+        .is_some()
+}
+
+/// Push an entity with a horizontal velocity for a duration, replacing any current impulse
+/// Only players, NPCs and creatures can be pushed
+pub fn impulse_apply(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    velocity: DbVector3,
+    duration_us: i64,
+) -> Result<(), String> {
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Entity not found")?;
+
+    if !matches!(
+        entity.kind,
+        DbEntityKind::Player | DbEntityKind::Npc | DbEntityKind::Creature
+    ) {
+        return Err(format!("{:?} entities cannot be pushed", entity.kind));
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let impulse = EntityImpulse {
+        entity_id,
+        velocity: DbVector3 {
+            x: velocity.x,
+            y: 0.0,
+            z: velocity.z,
+        },
+        started_at_us: current_time,
+        ends_at_us: current_time + duration_us,
+        last_tick_us: current_time,
+    };
+
+    if impulse_is_active(ctx, entity_id) {
+        ctx.db.entity_impulse().entity_id().update(impulse);
+    } else {
+        ctx.db.entity_impulse().insert(impulse);
+    }
+    Ok(())
+}
+
+/// Knock an entity directly away from a point
+pub fn impulse_knockback(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    from: &DbVector3,
+) -> Result<(), String> {
+    let entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Entity not found")?;
+
+    let away = DbVector3 {
+        x: entity.position.x - from.x,
+        y: 0.0,
+        z: entity.position.z - from.z,
+    };
+    let length = away.length();
+    if length <= f32::EPSILON {
+        return Ok(());
+    }
+
+    impulse_apply(
+        ctx,
+        entity_id,
+        away.scale(KNOCKBACK_SPEED / length),
+        KNOCKBACK_DURATION_US,
+    )
+}
+
+/// Move every pushed entity, keeping it on the NavMesh and out of building pieces,
+/// and correct pushed players
+#[spacetimedb::reducer]
+pub fn impulse_tick(ctx: &ReducerContext, _schedule: ImpulseSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `impulse_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let impulses: Vec<_> = ctx.db.entity_impulse().iter().collect();
+
+    for mut impulse in impulses {
+        let mut entity = match ctx.db.entity().entity_id().find(impulse.entity_id) {
+            Some(entity) => entity,
+            None => {
+                ctx.db
+                    .entity_impulse()
+                    .entity_id()
+                    .delete(impulse.entity_id);
+                continue;
+            }
+        };
+
+        // Integrate the linearly decaying velocity over the time since the last step
+        let duration = (impulse.ends_at_us - impulse.started_at_us).max(1) as f32;
+        let step_end = current_time.min(impulse.ends_at_us);
+        let fraction_at = |time: i64| 1.0 - (time - impulse.started_at_us) as f32 / duration;
+        let average_fraction = (fraction_at(impulse.last_tick_us) + fraction_at(step_end)) / 2.0;
+        let delta_secs = (step_end - impulse.last_tick_us).max(0) as f32 / 1_000_000.0;
+        let offset = impulse.velocity.scale(average_fraction * delta_secs);
+
+        let x = entity.position.x + offset.x;
+        let z = entity.position.z + offset.z;
+        let y = navmesh_surface_height(ctx, x, z).unwrap_or(entity.position.y);
+
+        // Stop at the edge of the walkable surface or at a building piece instead of passing it
+        let target = DbVector3 { x, y, z };
+        let lift = DbVector3 {
+            x: 0.0,
+            y: IMPULSE_COLLISION_HEIGHT,
+            z: 0.0,
+        };
+        let blocked = !is_position_valid(ctx, x, y, z)
+            || segment_hits_building_piece(
+                ctx,
+                &entity.position.add(&lift),
+                &target.add(&lift),
+                None,
+            )
+            .is_some();
+        if !blocked {
+            entity.position = target;
+            let entity = ctx.db.entity().entity_id().update(entity);
+
+            // Tell the client its position was overridden and accept it as valid
+            if let Some(mut player) = ctx.db.player().entity_id().find(entity.entity_id) {
+                player.last_valid_position = entity.position;
+                player.last_update_timestamp = current_time;
+                player.position_correction_seq = player.position_correction_seq.wrapping_add(1);
+                ctx.db.player().identity().update(player);
+            }
+        }
+
+        if blocked || current_time >= impulse.ends_at_us {
+            ctx.db
+                .entity_impulse()
+                .entity_id()
+                .delete(impulse.entity_id);
+        } else {
+            impulse.last_tick_us = current_time;
+            ctx.db.entity_impulse().entity_id().update(impulse);
+        }
+    }
+
+    Ok(())
+}
@@ -1,19 +1,45 @@
-use spacetimedb::{Identity, ReducerContext, SpacetimeType, Table};
 use crate::modules::admin::require_admin;
+use crate::modules::item_definition::item_definition;
+use spacetimedb::{Identity, ReducerContext, SpacetimeType, Table};
+use std::fmt;
+
+/// Number of slots in a new player inventory
+const INVENTORY_SIZE: u32 = 32;
 
-#[derive(SpacetimeType, Clone, Debug)]
+#[derive(SpacetimeType, Clone, Debug, PartialEq)]
 pub struct ItemRef {
+    /// References ItemDefinition.id
     pub id: u32,
     pub quantity: u32,
+    /// Remaining durability of this item instance (None for items that don't wear out)
+    /// Items with durability never stack, so every instance keeps its own
+    pub durability: Option<u32>,
 }
 
-#[spacetimedb::table(name = item, public)]
-pub struct Item {
-    #[primary_key]
-    pub id: u32,
-    pub name: String,
-    pub description: String,
-    pub weight: f32,
+impl ItemRef {
+    /// A quantity of an item without any instance state (costs, recipes, loot, totals)
+    pub fn new(id: u32, quantity: u32) -> Self {
+        Self {
+            id,
+            quantity,
+            durability: None,
+        }
+    }
+
+    /// A single item instance with its own durability
+    #[cfg(test)]
+    pub fn with_durability(id: u32, durability: u32) -> Self {
+        Self {
+            id,
+            quantity: 1,
+            durability: Some(durability),
+        }
+    }
+
+    /// Whether the item has worn out completely
+    pub fn is_broken(&self) -> bool {
+        self.durability == Some(0)
+    }
 }
 
 #[spacetimedb::table(name = inventory, public)]
@@ -21,65 +47,422 @@ pub struct Inventory {
     #[primary_key]
     pub identity: Identity,
     pub size: u32,
-    pub items: Vec<ItemRef>,
-}
-
-/// Initialize default items
-pub fn item_init(ctx: &ReducerContext) -> Result<(), String> {
-    // Branch - item_id 0
-    ctx.db.item().insert(Item {
-        id: 0,
-        name: "Branch".to_string(),
-        description: "A sturdy wooden branch.".to_string(),
-        weight: 0.5,
-    });
-
-    // Rock - item_id 1
-    ctx.db.item().insert(Item {
-        id: 1,
-        name: "Rock".to_string(),
-        description: "A solid rock.".to_string(),
-        weight: 1.0,
-    });
-
-    log::info!("Initialized default items");
-    Ok(())
+    /// One entry per slot (`size` entries), None for an empty slot
+    pub slots: Vec<Option<ItemRef>>,
+}
+
+/// Why an inventory add or remove was rejected (nothing is changed when it fails)
+#[derive(Clone, Debug, PartialEq)]
+pub enum InventoryError {
+    MissingInventory(Identity),
+    UnknownItem(u32),
+    InsufficientQuantity {
+        item_id: u32,
+        held: u64,
+        requested: u32,
+    },
+    /// The total held of an item would no longer fit in a u32
+    Overflow {
+        item_id: u32,
+    },
+    /// Not enough free stack space and empty slots
+    InventoryFull {
+        item_id: u32,
+        free_space: u64,
+        requested: u32,
+    },
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::MissingInventory(identity) => {
+                write!(f, "Inventory not found for {}", identity)
+            }
+            InventoryError::UnknownItem(item_id) => write!(f, "Item {} does not exist", item_id),
+            InventoryError::InsufficientQuantity {
+                item_id,
+                held,
+                requested,
+            } => write!(
+                f,
+                "Not enough of item {} in inventory (have {}, need {})",
+                item_id, held, requested
+            ),
+            InventoryError::Overflow { item_id } => {
+                write!(f, "Too many of item {} to hold", item_id)
+            }
+            InventoryError::InventoryFull {
+                item_id,
+                free_space,
+                requested,
+            } => write!(
+                f,
+                "Inventory full (room for {} of item {}, need {})",
+                free_space, item_id, requested
+            ),
+        }
+    }
+}
+
+impl From<InventoryError> for String {
+    fn from(err: InventoryError) -> Self {
+        err.to_string()
+    }
+}
+
+impl Inventory {
+    pub fn new(identity: Identity, size: u32) -> Self {
+        Self {
+            identity,
+            size,
+            slots: vec![None; size as usize],
+        }
+    }
+}
+
+impl ItemSlots for Inventory {
+    fn slots(&self) -> &[Option<ItemRef>] {
+        &self.slots
+    }
+
+    fn slots_mut(&mut self) -> &mut [Option<ItemRef>] {
+        &mut self.slots
+    }
+}
+
+/// Slot-based item storage with stack limits (player inventories and containers)
+pub trait ItemSlots {
+    fn slots(&self) -> &[Option<ItemRef>];
+    fn slots_mut(&mut self) -> &mut [Option<ItemRef>];
+
+    /// Total quantity of an item across all slots
+    fn item_quantity(&self, item_id: u32) -> u64 {
+        self.slots()
+            .iter()
+            .flatten()
+            .filter(|stack| stack.id == item_id)
+            .map(|stack| stack.quantity as u64)
+            .sum()
+    }
+
+    /// How many more of an item fit in its existing stacks and the empty slots
+    fn free_space(&self, item_id: u32, max_stack: u32) -> u64 {
+        let max_stack = max_stack.max(1);
+        self.slots()
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.id == item_id => {
+                    max_stack.saturating_sub(stack.quantity) as u64
+                }
+                Some(_) => 0,
+                None => max_stack as u64,
+            })
+            .sum()
+    }
+
+    /// Add a whole stack or nothing, topping up existing stacks before filling empty slots
+    fn add_stack(&mut self, item: &ItemRef, max_stack: u32) -> Result<(), InventoryError> {
+        let free_space = self.free_space(item.id, max_stack);
+        if free_space < item.quantity as u64 {
+            return Err(InventoryError::InventoryFull {
+                item_id: item.id,
+                free_space,
+                requested: item.quantity,
+            });
+        }
+        let overflow = self.add_stack_partial(item, max_stack)?;
+        debug_assert_eq!(overflow, 0);
+        Ok(())
+    }
+
+    /// Add as much of a stack as fits, topping up existing stacks before filling empty slots
+    /// Returns the quantity that did not fit
+    fn add_stack_partial(&mut self, item: &ItemRef, max_stack: u32) -> Result<u32, InventoryError> {
+        let item_id = item.id;
+        // Totals are reported as a u32 (see `inventory_get_item`), so never hold more than that
+        if self.item_quantity(item_id) + item.quantity as u64 > u32::MAX as u64 {
+            return Err(InventoryError::Overflow { item_id });
+        }
+
+        let max_stack = max_stack.max(1);
+        let mut remaining = item.quantity;
+
+        for stack in self.slots_mut().iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.id == item_id
+                && stack.durability == item.durability
+                && stack.quantity < max_stack
+            {
+                let added = (max_stack - stack.quantity).min(remaining);
+                stack.quantity += added;
+                remaining -= added;
+            }
+        }
+
+        for slot in self.slots_mut().iter_mut().filter(|slot| slot.is_none()) {
+            if remaining == 0 {
+                break;
+            }
+            let added = max_stack.min(remaining);
+            *slot = Some(ItemRef {
+                id: item_id,
+                quantity: added,
+                durability: item.durability,
+            });
+            remaining -= added;
+        }
+
+        Ok(remaining)
+    }
+
+    /// Remove items, taking from the last stacks first
+    /// Returns what was removed, one entry per distinct durability
+    fn remove_item(&mut self, item_id: u32, quantity: u32) -> Result<Vec<ItemRef>, InventoryError> {
+        let held = self.item_quantity(item_id);
+        if held < quantity as u64 {
+            return Err(InventoryError::InsufficientQuantity {
+                item_id,
+                held,
+                requested: quantity,
+            });
+        }
+
+        let mut removed: Vec<ItemRef> = Vec::new();
+        let mut remaining = quantity;
+        for slot in self.slots_mut().iter_mut().rev() {
+            if remaining == 0 {
+                break;
+            }
+            let emptied = match slot {
+                Some(stack) if stack.id == item_id => {
+                    let taken = stack.quantity.min(remaining);
+                    stack.quantity -= taken;
+                    remaining -= taken;
+                    match removed
+                        .iter_mut()
+                        .find(|item| item.durability == stack.durability)
+                    {
+                        Some(item) => item.quantity += taken,
+                        None => removed.push(ItemRef {
+                            id: item_id,
+                            quantity: taken,
+                            durability: stack.durability,
+                        }),
+                    }
+                    stack.quantity == 0
+                }
+                _ => false,
+            };
+            if emptied {
+                *slot = None;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn slot_index(&self, slot: u32) -> Result<usize, String> {
+        if (slot as usize) < self.slots().len() {
+            Ok(slot as usize)
+        } else {
+            Err(format!("Slot {} does not exist", slot))
+        }
+    }
+
+    /// The stack in a slot
+    fn stack(&self, slot: u32) -> Result<&ItemRef, String> {
+        let index = self.slot_index(slot)?;
+        self.slots()[index]
+            .as_ref()
+            .ok_or(format!("Slot {} is empty", slot))
+    }
+
+    /// Move as much of a stack as fits onto another stack of the same item
+    fn merge_stacks(&mut self, from: u32, to: u32, max_stack: u32) -> Result<(), String> {
+        let from_index = self.slot_index(from)?;
+        let to_index = self.slot_index(to)?;
+        if from_index == to_index {
+            return Err("Cannot merge a slot with itself".to_string());
+        }
+
+        let (source, target) = match (&self.slots()[from_index], &self.slots()[to_index]) {
+            (Some(source), Some(target)) => (source.clone(), target.clone()),
+            _ => return Err("Both slots must hold a stack to merge".to_string()),
+        };
+        if source.id != target.id || source.durability != target.durability {
+            return Err("Only stacks of the same item can be merged".to_string());
+        }
+
+        let moved = max_stack
+            .saturating_sub(target.quantity)
+            .min(source.quantity);
+        if moved == 0 {
+            return Err(format!("Slot {} is already a full stack", to));
+        }
+        self.slots_mut()[to_index] = Some(ItemRef {
+            quantity: target.quantity + moved,
+            ..target
+        });
+        self.slots_mut()[from_index] = (moved < source.quantity).then(|| ItemRef {
+            quantity: source.quantity - moved,
+            ..source
+        });
+        Ok(())
+    }
+
+    /// Move part of a stack into an empty slot
+    fn split_stack(&mut self, from: u32, to: u32, quantity: u32) -> Result<(), String> {
+        let from_index = self.slot_index(from)?;
+        let to_index = self.slot_index(to)?;
+        if self.slots()[to_index].is_some() {
+            return Err(format!("Slot {} is not empty", to));
+        }
+
+        let source = self.stack(from)?.clone();
+        if quantity == 0 || quantity >= source.quantity {
+            return Err(format!(
+                "Split quantity must be between 1 and {}",
+                source.quantity.saturating_sub(1)
+            ));
+        }
+
+        self.slots_mut()[from_index] = Some(ItemRef {
+            quantity: source.quantity - quantity,
+            ..source.clone()
+        });
+        self.slots_mut()[to_index] = Some(ItemRef { quantity, ..source });
+        Ok(())
+    }
+}
+
+/// Most of an item a single slot can hold
+pub fn item_max_stack(ctx: &ReducerContext, item_id: u32) -> Result<u32, InventoryError> {
+    ctx.db
+        .item_definition()
+        .id()
+        .find(item_id)
+        .map(|item| item.max_stack)
+        .ok_or(InventoryError::UnknownItem(item_id))
+}
+
+/// A new stack of an item, at full durability for items that wear out
+pub fn item_new_stack(
+    ctx: &ReducerContext,
+    item_id: u32,
+    quantity: u32,
+) -> Result<ItemRef, InventoryError> {
+    let definition = ctx
+        .db
+        .item_definition()
+        .id()
+        .find(item_id)
+        .ok_or(InventoryError::UnknownItem(item_id))?;
+    Ok(ItemRef {
+        id: item_id,
+        quantity,
+        durability: definition
+            .durability
+            .map(|durability| durability.max_durability),
+    })
+}
+
+pub fn inventory_find(
+    ctx: &ReducerContext,
+    identity: Identity,
+) -> Result<Inventory, InventoryError> {
+    ctx.db
+        .inventory()
+        .identity()
+        .find(identity)
+        .ok_or(InventoryError::MissingInventory(identity))
 }
 
 #[spacetimedb::reducer]
 pub fn inventory_create(ctx: &ReducerContext) -> Result<(), String> {
-    let inventory = Inventory {
-        identity: ctx.sender,
-        size: 32,
-        items: vec![],
-    };
+    let inventory = Inventory::new(ctx.sender, INVENTORY_SIZE);
     ctx.db.inventory().insert(inventory);
     Ok(())
 }
 
-/// Internal function for adding items (used by server-side logic like looting)
+/// Total weight of some stacks of items
+pub fn item_stacks_weight<'a>(
+    ctx: &ReducerContext,
+    stacks: impl IntoIterator<Item = &'a ItemRef>,
+) -> f32 {
+    stacks
+        .into_iter()
+        .filter_map(|stack| {
+            let item = ctx.db.item_definition().id().find(stack.id)?;
+            Some(item.weight * stack.quantity as f32)
+        })
+        .sum()
+}
+
+/// Total weight of everything in a player's inventory (0 without an inventory)
+pub fn inventory_weight(ctx: &ReducerContext, identity: Identity) -> f32 {
+    ctx.db
+        .inventory()
+        .identity()
+        .find(identity)
+        .map_or(0.0, |inventory| {
+            item_stacks_weight(ctx, inventory.slots.iter().flatten())
+        })
+}
+
+/// Internal function for adding new items (used by server-side logic like looting)
+/// Adds nothing and fails if not every item fits
 pub fn inventory_add_item_internal(
     ctx: &ReducerContext,
     identity: Identity,
     item_id: u32,
     quantity: u32,
-) -> Result<(), String> {
-    let inventory = ctx.db.inventory().identity().find(identity);
-    if let Some(mut inventory) = inventory {
-        if let Some(existing_item) = inventory.items.iter_mut().find(|item| item.id == item_id) {
-            existing_item.quantity += quantity;
-        } else {
-            let item_ref = ItemRef {
-                id: item_id,
-                quantity,
-            };
-            inventory.items.push(item_ref);
-        }
-        ctx.db.inventory().identity().update(inventory);
-    }
+) -> Result<(), InventoryError> {
+    let stack = item_new_stack(ctx, item_id, quantity)?;
+    inventory_add_stack_internal(ctx, identity, &stack)
+}
+
+/// Add as many new items as fit into a player's inventory
+/// Returns the quantity that did not fit
+pub fn inventory_add_item_partial(
+    ctx: &ReducerContext,
+    identity: Identity,
+    item_id: u32,
+    quantity: u32,
+) -> Result<u32, InventoryError> {
+    let stack = item_new_stack(ctx, item_id, quantity)?;
+    inventory_add_stack_partial(ctx, identity, &stack)
+}
+
+/// Add existing items (e.g., unequipped or traded), keeping their durability
+/// Adds nothing and fails if not every item fits
+pub fn inventory_add_stack_internal(
+    ctx: &ReducerContext,
+    identity: Identity,
+    item: &ItemRef,
+) -> Result<(), InventoryError> {
+    let mut inventory = inventory_find(ctx, identity)?;
+    let max_stack = item_max_stack(ctx, item.id)?;
+    inventory.add_stack(item, max_stack)?;
+    ctx.db.inventory().identity().update(inventory);
     Ok(())
 }
 
+/// Add as much of an existing stack as fits, keeping its durability
+/// Returns the quantity that did not fit
+pub fn inventory_add_stack_partial(
+    ctx: &ReducerContext,
+    identity: Identity,
+    item: &ItemRef,
+) -> Result<u32, InventoryError> {
+    let mut inventory = inventory_find(ctx, identity)?;
+    let max_stack = item_max_stack(ctx, item.id)?;
+    let overflow = inventory.add_stack_partial(item, max_stack)?;
+    ctx.db.inventory().identity().update(inventory);
+    Ok(overflow)
+}
+
 /// Add items to a player's inventory (admin-only reducer)
 #[spacetimedb::reducer]
 pub fn inventory_add_item(
@@ -89,41 +472,33 @@ pub fn inventory_add_item(
     quantity: u32,
 ) -> Result<(), String> {
     require_admin(ctx)?;
-    inventory_add_item_internal(ctx, identity, item_id, quantity)
+    Ok(inventory_add_item_internal(
+        ctx, identity, item_id, quantity,
+    )?)
 }
 
-pub fn inventory_get_item(ctx: &ReducerContext, item_id: u32) -> Result<ItemRef, String> {
-    let inventory = ctx.db.inventory().identity().find(ctx.sender);
-    if let Some(inventory) = inventory {
-        if let Some(item) = inventory.items.iter().find(|item| item.id == item_id) {
-            Ok(item.clone())
-        } else {
-            Err("Item not found in inventory".to_string())
-        }
-    } else {
-        Err("Inventory not found".to_string())
-    }
+/// Total quantity of an item in the sender's inventory (0 when none is held)
+pub fn inventory_get_item(ctx: &ReducerContext, item_id: u32) -> Result<ItemRef, InventoryError> {
+    let inventory = inventory_find(ctx, ctx.sender)?;
+    let quantity = u32::try_from(inventory.item_quantity(item_id))
+        .map_err(|_| InventoryError::Overflow { item_id })?;
+    Ok(ItemRef::new(item_id, quantity))
 }
 
 /// Internal function for removing items (used by server-side logic like building)
+/// Removes nothing and fails if not enough are held
+/// Returns the removed items with their durability
 pub fn inventory_remove_item_internal(
     ctx: &ReducerContext,
     identity: Identity,
     item_id: u32,
     quantity: u32,
-) -> Result<(), String> {
-    let inventory = ctx.db.inventory().identity().find(identity);
-    if let Some(mut inventory) = inventory {
-        if let Some(position) = inventory.items.iter().position(|item| item.id == item_id) {
-            let existing_item = &mut inventory.items[position];
-            existing_item.quantity -= quantity;
-            if existing_item.quantity == 0 {
-                inventory.items.remove(position);
-            }
-        }
-        ctx.db.inventory().identity().update(inventory);
-    }
-    Ok(())
+) -> Result<Vec<ItemRef>, InventoryError> {
+    let mut inventory = inventory_find(ctx, identity)?;
+    item_max_stack(ctx, item_id)?;
+    let removed = inventory.remove_item(item_id, quantity)?;
+    ctx.db.inventory().identity().update(inventory);
+    Ok(removed)
 }
 
 /// Remove items from a player's inventory (admin-only reducer)
@@ -135,5 +510,221 @@ pub fn inventory_remove_item(
     quantity: u32,
 ) -> Result<(), String> {
     require_admin(ctx)?;
-    inventory_remove_item_internal(ctx, identity, item_id, quantity)
+    inventory_remove_item_internal(ctx, identity, item_id, quantity)?;
+    Ok(())
+}
+
+/// Move a stack to another slot
+/// Merges into a stack of the same item, otherwise swaps the two slots
+#[spacetimedb::reducer]
+pub fn inventory_move_slot(ctx: &ReducerContext, from: u32, to: u32) -> Result<(), String> {
+    let mut inventory = inventory_find(ctx, ctx.sender)?;
+    let source = inventory.stack(from)?.clone();
+    let to_index = inventory.slot_index(to)?;
+
+    match &inventory.slots[to_index] {
+        Some(target)
+            if target.id == source.id && target.durability == source.durability && from != to =>
+        {
+            let max_stack = item_max_stack(ctx, source.id)?;
+            inventory.merge_stacks(from, to, max_stack)?;
+        }
+        _ => inventory.slots.swap(from as usize, to_index),
+    }
+
+    ctx.db.inventory().identity().update(inventory);
+    Ok(())
+}
+
+/// Split part of a stack off into an empty slot
+#[spacetimedb::reducer]
+pub fn inventory_split_stack(
+    ctx: &ReducerContext,
+    from: u32,
+    to: u32,
+    quantity: u32,
+) -> Result<(), String> {
+    let mut inventory = inventory_find(ctx, ctx.sender)?;
+    inventory.split_stack(from, to, quantity)?;
+    ctx.db.inventory().identity().update(inventory);
+    Ok(())
+}
+
+/// Move as much of a stack as fits onto another stack of the same item
+#[spacetimedb::reducer]
+pub fn inventory_merge_stacks(ctx: &ReducerContext, from: u32, to: u32) -> Result<(), String> {
+    let mut inventory = inventory_find(ctx, ctx.sender)?;
+    let max_stack = item_max_stack(ctx, inventory.stack(from)?.id)?;
+    inventory.merge_stacks(from, to, max_stack)?;
+    ctx.db.inventory().identity().update(inventory);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const MAX_STACKS: [u32; 3] = [1, 20, 50];
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Add { item_id: u32, quantity: u32 },
+        AddPartial { item_id: u32, quantity: u32 },
+        Remove { item_id: u32, quantity: u32 },
+        Split { from: u32, to: u32, quantity: u32 },
+        Merge { from: u32, to: u32 },
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        let item_id = 0..MAX_STACKS.len() as u32;
+        let quantity = prop_oneof![0..120u32, Just(u32::MAX)];
+        let slot = 0..10u32;
+        prop_oneof![
+            (item_id.clone(), quantity.clone())
+                .prop_map(|(item_id, quantity)| Op::Add { item_id, quantity }),
+            (item_id.clone(), quantity.clone())
+                .prop_map(|(item_id, quantity)| Op::AddPartial { item_id, quantity }),
+            (item_id, quantity).prop_map(|(item_id, quantity)| Op::Remove { item_id, quantity }),
+            (slot.clone(), slot.clone(), 0..60u32).prop_map(|(from, to, quantity)| Op::Split {
+                from,
+                to,
+                quantity
+            }),
+            (slot.clone(), slot).prop_map(|(from, to)| Op::Merge { from, to }),
+        ]
+    }
+
+    fn max_stack(item_id: u32) -> u32 {
+        MAX_STACKS[item_id as usize]
+    }
+
+    fn totals(inventory: &Inventory) -> Vec<u64> {
+        (0..MAX_STACKS.len() as u32)
+            .map(|item_id| inventory.item_quantity(item_id))
+            .collect()
+    }
+
+    #[test]
+    fn add_fills_existing_stacks_before_empty_slots() {
+        let mut inventory = Inventory::new(Identity::ZERO, 3);
+        inventory.slots[1] = Some(ItemRef::new(0, 45));
+
+        inventory.add_stack(&ItemRef::new(0, 10), 50).unwrap();
+
+        assert_eq!(
+            inventory.slots,
+            vec![Some(ItemRef::new(0, 5)), Some(ItemRef::new(0, 50)), None,]
+        );
+    }
+
+    #[test]
+    fn add_fails_without_changes_when_full() {
+        let mut inventory = Inventory::new(Identity::ZERO, 2);
+        inventory.add_stack(&ItemRef::new(0, 60), 50).unwrap();
+        let before = inventory.slots.clone();
+
+        let err = inventory.add_stack(&ItemRef::new(0, 41), 50).unwrap_err();
+
+        assert_eq!(
+            err,
+            InventoryError::InventoryFull {
+                item_id: 0,
+                free_space: 40,
+                requested: 41
+            }
+        );
+        assert_eq!(inventory.slots, before);
+        assert_eq!(inventory.add_stack_partial(&ItemRef::new(0, 41), 50), Ok(1));
+    }
+
+    #[test]
+    fn remove_more_than_held_is_rejected() {
+        let mut inventory = Inventory::new(Identity::ZERO, 4);
+        inventory.add_stack(&ItemRef::new(1, 5), 20).unwrap();
+
+        assert_eq!(
+            inventory.remove_item(1, 6),
+            Err(InventoryError::InsufficientQuantity {
+                item_id: 1,
+                held: 5,
+                requested: 6
+            })
+        );
+        assert_eq!(inventory.item_quantity(1), 5);
+    }
+
+    #[test]
+    fn durable_items_keep_their_own_durability() {
+        let mut inventory = Inventory::new(Identity::ZERO, 4);
+        let worn = ItemRef::with_durability(5, 40);
+        let new = ItemRef::with_durability(5, 150);
+        inventory.add_stack(&worn, 1).unwrap();
+        inventory.add_stack(&new, 1).unwrap();
+
+        assert!(inventory.merge_stacks(1, 0, 1).is_err());
+        assert_eq!(inventory.remove_item(5, 1), Ok(vec![new]));
+        assert_eq!(inventory.slots[0], Some(worn));
+    }
+
+    proptest! {
+        #[test]
+        fn random_operations_never_create_or_destroy_items(
+            ops in proptest::collection::vec(op(), 1..80)
+        ) {
+            let mut inventory = Inventory::new(Identity::ZERO, 10);
+            let mut expected = vec![0u64; MAX_STACKS.len()];
+
+            for op in ops {
+                let before = inventory.slots.clone();
+                match op {
+                    Op::Add { item_id, quantity } => {
+                        match inventory.add_stack(&ItemRef::new(item_id, quantity), max_stack(item_id)) {
+                            Ok(()) => expected[item_id as usize] += quantity as u64,
+                            Err(_) => prop_assert_eq!(&inventory.slots, &before),
+                        }
+                    }
+                    Op::AddPartial { item_id, quantity } => {
+                        match inventory.add_stack_partial(&ItemRef::new(item_id, quantity), max_stack(item_id)) {
+                            Ok(overflow) => {
+                                prop_assert!(overflow <= quantity);
+                                expected[item_id as usize] += (quantity - overflow) as u64;
+                            }
+                            Err(_) => prop_assert_eq!(&inventory.slots, &before),
+                        }
+                    }
+                    Op::Remove { item_id, quantity } => {
+                        let held = expected[item_id as usize];
+                        match inventory.remove_item(item_id, quantity) {
+                            Ok(_) => expected[item_id as usize] -= quantity as u64,
+                            Err(_) => {
+                                prop_assert!(held < quantity as u64);
+                                prop_assert_eq!(&inventory.slots, &before);
+                            }
+                        }
+                    }
+                    Op::Split { from, to, quantity } => {
+                        if inventory.split_stack(from, to, quantity).is_err() {
+                            prop_assert_eq!(&inventory.slots, &before);
+                        }
+                    }
+                    Op::Merge { from, to } => {
+                        let max_stack = inventory
+                            .stack(from)
+                            .map_or(1, |stack| max_stack(stack.id));
+                        if inventory.merge_stacks(from, to, max_stack).is_err() {
+                            prop_assert_eq!(&inventory.slots, &before);
+                        }
+                    }
+                }
+
+                prop_assert_eq!(totals(&inventory), expected.clone());
+                prop_assert_eq!(inventory.slots.len(), 10);
+                for stack in inventory.slots.iter().flatten() {
+                    prop_assert!(stack.quantity >= 1);
+                    prop_assert!(stack.quantity <= max_stack(stack.id));
+                }
+            }
+        }
+    }
 }
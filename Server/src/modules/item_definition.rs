@@ -0,0 +1,436 @@
+use crate::modules::admin::require_admin;
+use crate::modules::boss::boss_definition;
+use crate::modules::building_piece_variant::building_piece_variant;
+use crate::modules::combat::{DbDamageType, DbResistances};
+use crate::modules::crafting::recipe;
+use crate::modules::equipment::{DbEquipmentSlot, DbEquippable, DbStats};
+use crate::modules::inventory::ItemRef;
+use crate::modules::lootable::lootable_item_type;
+use crate::modules::npc::npc_definition;
+use crate::modules::projectile::projectile_type;
+use spacetimedb::{ReducerContext, SpacetimeType, Table};
+
+/// Broad grouping of items, used for sorting and filtering
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbItemCategory {
+    /// Gathered materials used for building and crafting
+    Resource,
+    /// Consumed when launched as a projectile
+    Ammunition,
+    Weapon,
+    Tool,
+    Armor,
+    Consumable,
+    Misc,
+}
+
+/// How an item wears out and what repairing it costs
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbDurability {
+    /// Durability of a new or fully repaired item
+    pub max_durability: u32,
+    /// Taken from the inventory for every repair
+    pub repair_materials: Vec<ItemRef>,
+}
+
+/// The single definition of an item, referenced by id everywhere items appear
+/// (inventories, lootables, building costs, loot tables, recipes, projectile types)
+#[spacetimedb::table(name = item_definition, public)]
+pub struct ItemDefinition {
+    #[primary_key]
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    /// Weight of a single item
+    pub weight: f32,
+    pub category: DbItemCategory,
+    /// Free-form labels (e.g., "wood", "fuel")
+    pub tags: Vec<String>,
+    /// Most of this item a single slot can hold (1 = not stackable)
+    pub max_stack: u32,
+    /// Key the client uses to look up the item's icon
+    pub icon_key: String,
+    /// Slot and stat bonuses for items that can be equipped
+    pub equipment: Option<DbEquippable>,
+    /// Wear and repair cost for items that break with use (these never stack)
+    pub durability: Option<DbDurability>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn item_definition_insert(
+    ctx: &ReducerContext,
+    id: u32,
+    name: &str,
+    description: &str,
+    weight: f32,
+    category: DbItemCategory,
+    tags: &[&str],
+    max_stack: u32,
+    equipment: Option<DbEquippable>,
+    durability: Option<DbDurability>,
+) {
+    ctx.db.item_definition().insert(ItemDefinition {
+        id,
+        name: name.to_string(),
+        description: description.to_string(),
+        weight,
+        category,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        max_stack,
+        icon_key: format!("item_{}", name.to_lowercase().replace(' ', "_")),
+        equipment,
+        durability,
+    });
+}
+
+/// Initialize default item definitions
+pub fn item_definition_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Branch - item_id 0
+    item_definition_insert(
+        ctx,
+        0,
+        "Branch",
+        "A sturdy wooden branch.",
+        0.5,
+        DbItemCategory::Resource,
+        &["wood", "fuel"],
+        50,
+        None,
+        None,
+    );
+
+    // Rock - item_id 1
+    item_definition_insert(
+        ctx,
+        1,
+        "Rock",
+        "A solid rock.",
+        1.0,
+        DbItemCategory::Resource,
+        &["stone"],
+        50,
+        None,
+        None,
+    );
+
+    // Wood - item_id 2
+    item_definition_insert(
+        ctx,
+        2,
+        "Wood",
+        "Timber cut from a tree.",
+        2.0,
+        DbItemCategory::Resource,
+        &["wood", "fuel"],
+        50,
+        None,
+        None,
+    );
+
+    // Arrow - item_id 3
+    item_definition_insert(
+        ctx,
+        3,
+        "Arrow",
+        "A fletched arrow for ranged weapons.",
+        0.1,
+        DbItemCategory::Ammunition,
+        &["projectile"],
+        100,
+        None,
+        None,
+    );
+
+    // Hide - item_id 4
+    item_definition_insert(
+        ctx,
+        4,
+        "Hide",
+        "A tough animal hide.",
+        0.5,
+        DbItemCategory::Resource,
+        &["leather"],
+        20,
+        None,
+        None,
+    );
+
+    // Wooden Club - item_id 5
+    item_definition_insert(
+        ctx,
+        5,
+        "Wooden Club",
+        "A heavy length of wood. Hits harder than bare fists.",
+        2.5,
+        DbItemCategory::Weapon,
+        &["wood", "blunt"],
+        1,
+        Some(DbEquippable {
+            slot: DbEquipmentSlot::MainHand,
+            bonuses: DbStats {
+                attack_damage: 6.0,
+                attack_range: 0.5,
+                ..Default::default()
+            },
+            damage_type: Some(DbDamageType::Blunt),
+        }),
+        Some(DbDurability {
+            max_durability: 150,
+            repair_materials: vec![ItemRef::new(2, 2)], // Wood
+        }),
+    );
+
+    // Hide Cap - item_id 6
+    item_definition_insert(
+        ctx,
+        6,
+        "Hide Cap",
+        "A simple cap stitched from animal hide.",
+        0.5,
+        DbItemCategory::Armor,
+        &["leather"],
+        1,
+        Some(DbEquippable {
+            slot: DbEquipmentSlot::Head,
+            bonuses: DbStats {
+                armor: 5.0,
+                resistances: DbResistances {
+                    slash: 0.05,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            damage_type: None,
+        }),
+        Some(DbDurability {
+            max_durability: 100,
+            repair_materials: vec![ItemRef::new(4, 1)], // Hide
+        }),
+    );
+
+    // Hide Tunic - item_id 7
+    item_definition_insert(
+        ctx,
+        7,
+        "Hide Tunic",
+        "A tunic of layered hides.",
+        2.0,
+        DbItemCategory::Armor,
+        &["leather"],
+        1,
+        Some(DbEquippable {
+            slot: DbEquipmentSlot::Chest,
+            bonuses: DbStats {
+                armor: 10.0,
+                max_health: 10.0,
+                resistances: DbResistances {
+                    slash: 0.1,
+                    pierce: 0.05,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            damage_type: None,
+        }),
+        Some(DbDurability {
+            max_durability: 150,
+            repair_materials: vec![ItemRef::new(4, 2)], // Hide
+        }),
+    );
+
+    // Hide Boots - item_id 8
+    item_definition_insert(
+        ctx,
+        8,
+        "Hide Boots",
+        "Light boots that make for quick footing.",
+        1.0,
+        DbItemCategory::Armor,
+        &["leather"],
+        1,
+        Some(DbEquippable {
+            slot: DbEquipmentSlot::Feet,
+            bonuses: DbStats {
+                armor: 3.0,
+                movement_speed: 0.5,
+                resistances: DbResistances {
+                    pierce: 0.05,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            damage_type: None,
+        }),
+        Some(DbDurability {
+            max_durability: 100,
+            repair_materials: vec![ItemRef::new(4, 1)], // Hide
+        }),
+    );
+
+    log::info!("Initialized default item definitions");
+    Ok(())
+}
+
+pub fn item_definition_get(ctx: &ReducerContext, item_id: u32) -> Result<ItemDefinition, String> {
+    ctx.db
+        .item_definition()
+        .id()
+        .find(item_id)
+        .ok_or(format!("Item {} does not exist", item_id))
+}
+
+/// Fail unless every item id has a definition
+pub fn item_definition_check_all(
+    ctx: &ReducerContext,
+    item_ids: impl IntoIterator<Item = u32>,
+) -> Result<(), String> {
+    for item_id in item_ids {
+        item_definition_get(ctx, item_id)?;
+    }
+    Ok(())
+}
+
+/// Check that every item referenced by other definitions resolves (run at the end of init)
+pub fn item_definition_validate(ctx: &ReducerContext) -> Result<(), String> {
+    let mut references: Vec<(String, u32)> = Vec::new();
+
+    for lootable in ctx.db.lootable_item_type().iter() {
+        references.push((
+            format!("lootable type {}", lootable.type_id),
+            lootable.item_id,
+        ));
+    }
+    for variant in ctx.db.building_piece_variant().iter() {
+        for cost in &variant.build_cost {
+            references.push((
+                format!("building piece variant {} cost", variant.variant_id),
+                cost.item_id,
+            ));
+        }
+    }
+    for definition in ctx.db.npc_definition().iter() {
+        for entry in &definition.loot_table {
+            references.push((
+                format!("NPC definition {} loot", definition.npc_def_id),
+                entry.item_id,
+            ));
+        }
+    }
+    for definition in ctx.db.boss_definition().iter() {
+        for entry in &definition.loot_table {
+            references.push((
+                format!("boss definition {} loot", definition.boss_def_id),
+                entry.item_id,
+            ));
+        }
+    }
+    for recipe in ctx.db.recipe().iter() {
+        for item in recipe.inputs.iter().chain(&recipe.outputs) {
+            references.push((format!("recipe {}", recipe.recipe_id), item.id));
+        }
+    }
+    for definition in ctx.db.item_definition().iter() {
+        for material in definition
+            .durability
+            .iter()
+            .flat_map(|durability| &durability.repair_materials)
+        {
+            references.push((
+                format!("item {} repair materials", definition.id),
+                material.id,
+            ));
+        }
+    }
+    for projectile_type in ctx.db.projectile_type().iter() {
+        references.push(("projectile type".to_string(), projectile_type.item_id));
+    }
+
+    let unresolved: Vec<_> = references
+        .into_iter()
+        .filter(|(_, item_id)| ctx.db.item_definition().id().find(*item_id).is_none())
+        .map(|(source, item_id)| format!("{} references unknown item {}", source, item_id))
+        .collect();
+    if !unresolved.is_empty() {
+        return Err(format!(
+            "Unresolved item references: {}",
+            unresolved.join("; ")
+        ));
+    }
+
+    log::info!("Validated item references");
+    Ok(())
+}
+
+/// Creates a new item definition (admin only)
+#[spacetimedb::reducer]
+#[allow(clippy::too_many_arguments)]
+pub fn item_definition_create(
+    ctx: &ReducerContext,
+    id: u32,
+    name: String,
+    description: String,
+    weight: f32,
+    category: DbItemCategory,
+    tags: Vec<String>,
+    max_stack: u32,
+    icon_key: String,
+    equipment: Option<DbEquippable>,
+    durability: Option<DbDurability>,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx.db.item_definition().id().find(id).is_some() {
+        return Err(format!("Item {} already exists", id));
+    }
+    if weight < 0.0 {
+        return Err("Item weight cannot be negative".to_string());
+    }
+    if max_stack == 0 {
+        return Err("Max stack must be at least 1".to_string());
+    }
+    if equipment.is_some() && max_stack != 1 {
+        return Err("Equippable items cannot stack".to_string());
+    }
+    if let Some(equippable) = &equipment {
+        if equippable.damage_type.is_some() && equippable.slot != DbEquipmentSlot::MainHand {
+            return Err("Only main hand items can have a damage type".to_string());
+        }
+    }
+    if let Some(durability) = &durability {
+        if max_stack != 1 {
+            return Err("Items with durability cannot stack".to_string());
+        }
+        if durability.max_durability == 0 {
+            return Err("Max durability must be at least 1".to_string());
+        }
+        if durability
+            .repair_materials
+            .iter()
+            .any(|material| material.id == id)
+        {
+            return Err("An item cannot be repaired with itself".to_string());
+        }
+        item_definition_check_all(
+            ctx,
+            durability
+                .repair_materials
+                .iter()
+                .map(|material| material.id),
+        )?;
+    }
+
+    ctx.db.item_definition().insert(ItemDefinition {
+        id,
+        name,
+        description,
+        weight,
+        category,
+        tags,
+        max_stack,
+        icon_key,
+        equipment,
+        durability,
+    });
+    log::info!("Created item definition with id: {}", id);
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::modules::building_piece_placed::building_piece_placed;
+use crate::modules::building_piece_variant::building_piece_variant;
+use crate::modules::navmesh::navmesh_surface_height;
+use crate::types::DbVector3;
+use spacetimedb::{ReducerContext, Table};
+
+/// Height above an entity's position that sight lines are traced from and to
+pub const EYE_HEIGHT: f32 = 1.5;
+/// Distance between terrain height samples along a sight line
+const TERRAIN_SAMPLE_SPACING: f32 = 0.5;
+/// How far below the walkable surface a sample may dip before the terrain blocks it
+const TERRAIN_TOLERANCE: f32 = 0.25;
+
+/// Check if one entity can see another
+/// Traces from eye height to eye height against placed building pieces and terrain
+/// The ignored piece (e.g. the one being targeted) never blocks the line
+pub fn has_line_of_sight(
+    ctx: &ReducerContext,
+    from: &DbVector3,
+    to: &DbVector3,
+    ignore_piece_id: Option<u32>,
+) -> bool {
+    let eye_offset = DbVector3 {
+        x: 0.0,
+        y: EYE_HEIGHT,
+        z: 0.0,
+    };
+    let start = from.add(&eye_offset);
+    let end = to.add(&eye_offset);
+
+    segment_hits_building_piece(ctx, &start, &end, ignore_piece_id).is_none()
+        && segment_hits_terrain(ctx, &start, &end).is_none()
+}
+
+/// Find the nearest placed building piece whose bounds the segment passes through
+/// Returns the piece_id and the fraction along the segment where it is hit
+pub fn segment_hits_building_piece(
+    ctx: &ReducerContext,
+    start: &DbVector3,
+    end: &DbVector3,
+    ignore_piece_id: Option<u32>,
+) -> Option<(u32, f32)> {
+    // Bounding sphere of the segment for cheap rejection
+    let midpoint = start.lerp(end, 0.5);
+    let half_length = start.distance(end) / 2.0;
+
+    ctx.db
+        .building_piece_placed()
+        .iter()
+        .filter(|piece| Some(piece.piece_id) != ignore_piece_id)
+        .filter_map(|piece| {
+            let variant = ctx
+                .db
+                .building_piece_variant()
+                .variant_id()
+                .find(piece.variant_id)?;
+
+            variant
+                .bounds
+                .iter()
+                .filter(|bounds| {
+                    piece.position.distance(&midpoint) <= half_length + bounds.bounding_radius()
+                })
+                .filter_map(|bounds| {
+                    bounds.segment_intersection(&piece.position, &piece.rotation, start, end)
+                })
+                .reduce(f32::min)
+                .map(|fraction| (piece.piece_id, fraction))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Find the first sample point where the segment passes below the walkable surface
+/// Returns the fraction along the segment where the terrain is hit
+pub fn segment_hits_terrain(
+    ctx: &ReducerContext,
+    start: &DbVector3,
+    end: &DbVector3,
+) -> Option<f32> {
+    let samples = (start.distance(end) / TERRAIN_SAMPLE_SPACING)
+        .ceil()
+        .max(1.0) as u32;
+
+    (0..=samples)
+        .map(|i| i as f32 / samples as f32)
+        .find(|fraction| {
+            let point = start.lerp(end, *fraction);
+            match navmesh_surface_height(ctx, point.x, point.z) {
+                Some(surface_y) => point.y < surface_y - TERRAIN_TOLERANCE,
+                None => false,
+            }
+        })
+}
@@ -1,22 +1,24 @@
 use crate::modules::admin::require_admin;
+use crate::modules::encumbrance::encumbrance_check_loot;
 use crate::modules::entity::entity;
+use crate::modules::durability::durability_on_gather;
 use crate::modules::inventory::inventory_add_item_internal;
+use crate::modules::item_definition::item_definition_get;
 use crate::modules::player::player;
+use crate::modules::player_stats::player_stats_on_loot;
+use crate::modules::zone::zone_check_loot;
 use crate::types::DbVector3;
 use spacetimedb::{ReducerContext, Table};
 
-/// Defines a type of lootable item (e.g., "Rock", "Stick")
-/// The type_id also serves as the item_id for inventory
+/// Defines a type of lootable object in the world (e.g., "Rock", "Tree")
 #[spacetimedb::table(name = lootable_item_type, public)]
 pub struct LootableItemType {
     #[primary_key]
     pub type_id: u32,
-    /// Display name of the item
+    /// Display name of the lootable object
     pub name: String,
-    /// Description of the item
-    pub description: String,
-    /// Weight of a single item
-    pub weight: f32,
+    /// Item given when looted (references ItemDefinition.id)
+    pub item_id: u32,
     /// Quantity given when looted
     pub quantity: u32,
     /// Time in microseconds before the item respawns
@@ -45,35 +47,34 @@ pub struct LootableSpawn {
 }
 
 /// Initialize default lootable item types and spawns
+// Spawn coordinates are copied verbatim from the editor
+#[allow(clippy::excessive_precision)]
 pub fn lootable_item_type_init(ctx: &ReducerContext) -> Result<(), String> {
-    // Branch - type_id 0 (also used as item_id)
+    // Branch - type_id 0
     ctx.db.lootable_item_type().insert(LootableItemType {
         type_id: 0,
         name: "Branch".to_string(),
-        description: "A fallen tree branch. Useful for crafting.".to_string(),
-        weight: 0.5,
+        item_id: 0, // Branch
         quantity: 5,
         respawn_time_us: 30_000_000, // 30 seconds
         loot_distance: 1.5,
     });
 
-    // Rock - type_id 1 (also used as item_id)
+    // Rock - type_id 1
     ctx.db.lootable_item_type().insert(LootableItemType {
         type_id: 1,
         name: "Rock".to_string(),
-        description: "A small stone. Can be used for tools or building.".to_string(),
-        weight: 1.0,
+        item_id: 1, // Rock
         quantity: 5,
         respawn_time_us: 30_000_000, // 30 seconds
         loot_distance: 1.5,
     });
 
-    // Tree - type_id 2 (also used as item_id)
+    // Tree - type_id 2
     ctx.db.lootable_item_type().insert(LootableItemType {
         type_id: 2,
         name: "Tree".to_string(),
-        description: "A harvestable tree. Provides wood for construction.".to_string(),
-        weight: 2.0,
+        item_id: 2, // Wood
         quantity: 15,
         respawn_time_us: 30_000_000, // 30 seconds
         loot_distance: 2.5,
@@ -127,19 +128,18 @@ pub fn lootable_create_type(
     ctx: &ReducerContext,
     type_id: u32,
     name: String,
-    description: String,
-    weight: f32,
+    item_id: u32,
     quantity: u32,
     respawn_time_seconds: f32,
     loot_distance: f32,
 ) -> Result<(), String> {
     require_admin(ctx)?;
+    item_definition_get(ctx, item_id)?;
     let respawn_time_us = (respawn_time_seconds * 1_000_000.0) as i64;
     let item_type = LootableItemType {
         type_id,
         name,
-        description,
-        weight,
+        item_id,
         quantity,
         respawn_time_us,
         loot_distance,
@@ -201,7 +201,7 @@ pub fn lootable_loot(ctx: &ReducerContext, spawn_id: u32) -> Result<(), String>
         .db
         .entity()
         .entity_id()
-        .find(&player.entity_id)
+        .find(player.entity_id)
         .ok_or("Player entity not found")?;
 
     // Find the spawn point
@@ -228,6 +228,12 @@ pub fn lootable_loot(ctx: &ReducerContext, spawn_id: u32) -> Result<(), String>
         ));
     }
 
+    // Some zones don't allow looting
+    zone_check_loot(ctx, &spawn.position)?;
+
+    // Overloaded players can't pick anything else up
+    encumbrance_check_loot(ctx, &player)?;
+
     let current_time = ctx.timestamp.to_micros_since_unix_epoch();
 
     // Check if already looted (on cooldown)
@@ -252,8 +258,10 @@ pub fn lootable_loot(ctx: &ReducerContext, spawn_id: u32) -> Result<(), String>
     spawn.looted_at_us = current_time;
     ctx.db.lootable_spawn().spawn_id().update(spawn);
 
-    // Add item to player's inventory (type_id is used as item_id)
-    inventory_add_item_internal(ctx, ctx.sender, item_type.type_id, item_type.quantity)?;
+    // Add the lootable's item to player's inventory
+    inventory_add_item_internal(ctx, ctx.sender, item_type.item_id, item_type.quantity)?;
+    durability_on_gather(ctx, ctx.sender);
+    player_stats_on_loot(ctx, ctx.sender, item_type.item_id, item_type.quantity);
 
     log::info!(
         "Player {:?} looted spawn {} ({})",
@@ -1,10 +1,32 @@
 pub mod admin;
+pub mod boss;
 pub mod building_piece_placed;
 pub mod building_piece_variant;
+pub mod combat;
+pub mod container;
+pub mod crafting;
 pub mod creative_camera;
+pub mod dropped_item;
+pub mod duel;
+pub mod durability;
+pub mod encumbrance;
 pub mod entity;
+pub mod equipment;
+pub mod faction;
+pub mod impulse;
 pub mod inventory;
+pub mod item_definition;
+pub mod line_of_sight;
 pub mod lootable;
 pub mod navmesh;
+pub mod npc;
 pub mod player;
+pub mod player_stats;
+pub mod projectile;
+pub mod pvp;
+pub mod stamina;
+pub mod status_effect;
+pub mod threat;
+pub mod trade;
 pub mod world_spawn;
+pub mod zone;
@@ -66,7 +66,7 @@ pub fn navmesh_set_config(
     require_admin(ctx)?;
 
     // Check if config already exists
-    if let Some(mut config) = ctx.db.navmesh_config().id().find(&0) {
+    if let Some(mut config) = ctx.db.navmesh_config().id().find(0) {
         config.cell_size = cell_size;
         config.z_tolerance = z_tolerance;
         config.bounds_min_x = bounds_min_x;
@@ -97,18 +97,49 @@ pub fn navmesh_clear_grid(ctx: &ReducerContext) -> Result<(), String> {
     // Delete all grid points
     let points: Vec<_> = ctx.db.navmesh_grid().iter().collect();
     for point in points {
-        ctx.db.navmesh_grid().id().delete(&point.id);
+        ctx.db.navmesh_grid().id().delete(point.id);
     }
 
     log::info!("NavMesh grid cleared");
     Ok(())
 }
 
+/// Calculate the grid cell containing a world position (matching Unity's export logic)
+pub fn navmesh_grid_coords(config: &NavMeshConfig, x: f32, z: f32) -> (i32, i32) {
+    let grid_x = ((x - config.bounds_min_x) / config.cell_size).floor() as i32;
+    let grid_z = ((z - config.bounds_min_z) / config.cell_size).floor() as i32;
+    (grid_x, grid_z)
+}
+
+/// Find all NavMesh points in a single grid cell
+pub fn navmesh_points_in_cell(
+    ctx: &ReducerContext,
+    grid_x: i32,
+    grid_z: i32,
+) -> impl Iterator<Item = NavMeshGrid> + '_ {
+    ctx.db
+        .navmesh_grid()
+        .grid_x()
+        .filter(grid_x)
+        .filter(move |p| p.grid_z == grid_z)
+}
+
+/// Get the lowest walkable surface height in the cell containing (x, z)
+/// Returns None if there is no NavMesh config or no surface in that cell
+pub fn navmesh_surface_height(ctx: &ReducerContext, x: f32, z: f32) -> Option<f32> {
+    let config = ctx.db.navmesh_config().id().find(0)?;
+    let (grid_x, grid_z) = navmesh_grid_coords(&config, x, z);
+
+    navmesh_points_in_cell(ctx, grid_x, grid_z)
+        .map(|point| point.y)
+        .reduce(f32::min)
+}
+
 /// Validate if a position is on a walkable surface
 /// Returns true if the position is within z_tolerance of a valid NavMesh point
 pub fn is_position_valid(ctx: &ReducerContext, x: f32, y: f32, z: f32) -> bool {
     // Get config
-    let config = match ctx.db.navmesh_config().id().find(&0) {
+    let config = match ctx.db.navmesh_config().id().find(0) {
         Some(cfg) => cfg,
         None => {
             log::warn!("NavMesh config not found, position validation disabled");
@@ -117,25 +148,13 @@ pub fn is_position_valid(ctx: &ReducerContext, x: f32, y: f32, z: f32) -> bool {
     };
 
     // Calculate grid coordinates for this position (matching Unity's export logic)
-    let grid_x = ((x - config.bounds_min_x) / config.cell_size).floor() as i32;
-    let grid_z = ((z - config.bounds_min_z) / config.cell_size).floor() as i32;
+    let (grid_x, grid_z) = navmesh_grid_coords(&config, x, z);
 
     // Check the target cell and adjacent cells (3x3 grid)
     for dx in -1..=1 {
         for dz in -1..=1 {
-            let check_grid_x = grid_x + dx;
-            let check_grid_z = grid_z + dz;
-
-            // Find all points in this grid cell
-            let points: Vec<_> = ctx
-                .db
-                .navmesh_grid()
-                .iter()
-                .filter(|p| p.grid_x == check_grid_x && p.grid_z == check_grid_z)
-                .collect();
-
-            // Check if any point is within tolerance
-            for point in points {
+            // Check if any point in this grid cell is within tolerance
+            for point in navmesh_points_in_cell(ctx, grid_x + dx, grid_z + dz) {
                 let horizontal_dist_sq = (x - point.x).powi(2) + (z - point.z).powi(2);
                 let vertical_dist = (y - point.y).abs();
 
@@ -156,7 +175,7 @@ pub fn is_position_valid(ctx: &ReducerContext, x: f32, y: f32, z: f32) -> bool {
 #[spacetimedb::reducer]
 pub fn navmesh_get_stats(ctx: &ReducerContext) -> Result<(), String> {
     let point_count = ctx.db.navmesh_grid().iter().count();
-    let config = ctx.db.navmesh_config().id().find(&0);
+    let config = ctx.db.navmesh_config().id().find(0);
 
     if let Some(cfg) = config {
         log::info!(
@@ -0,0 +1,762 @@
+use crate::modules::admin::require_admin;
+use crate::modules::boss::{boss_on_killed, boss_on_leash};
+use crate::modules::combat::{is_staggered, DbDamageType};
+use crate::modules::entity::{
+    entity, entity_apply_damage_internal, entity_create, entity_delete, entity_find_in_radius,
+    DbEntityKind, Entity,
+};
+use crate::modules::faction::{faction_relationship_between, DbFactionRelationship};
+use crate::modules::impulse::impulse_is_active;
+use crate::modules::inventory::{inventory_add_item_partial, ItemRef};
+use crate::modules::item_definition::item_definition_check_all;
+use crate::modules::line_of_sight::has_line_of_sight;
+use crate::modules::navmesh::{is_position_valid, navmesh_surface_height};
+use crate::modules::player::player;
+use crate::modules::player_stats::player_stats_on_loot;
+use crate::modules::threat::{
+    threat_add, threat_clear_npc, threat_on_damage, threat_top_target, AGGRO_THREAT,
+};
+use crate::modules::zone::zone_speed_multiplier_at;
+use crate::types::DbVector3;
+use spacetimedb::rand::Rng;
+use spacetimedb::{ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// How often NPC brains are ticked (200ms)
+const NPC_AI_TICK_INTERVAL_US: i64 = 200_000;
+/// Distance at which a movement goal counts as reached
+const ARRIVAL_DISTANCE: f32 = 0.5;
+/// Shortest and longest time an NPC idles before wandering
+const IDLE_MIN_US: i64 = 2_000_000;
+const IDLE_MAX_US: i64 = 6_000_000;
+
+/// A possible drop from a loot table
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbLootEntry {
+    /// References ItemDefinition.id
+    pub item_id: u32,
+    pub min_quantity: u32,
+    pub max_quantity: u32,
+    /// Probability of this entry dropping (0.0 - 1.0)
+    pub chance: f32,
+}
+
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbNpcState {
+    /// Standing at its current position
+    Idle,
+    /// Walking to a random point near home
+    Wander,
+    /// Moving towards its target
+    Chase,
+    /// In range of its target and attacking
+    Attack,
+    /// Walking back home after leaving its leash radius
+    Return,
+}
+
+/// Defines a type of NPC or creature (e.g., "Wolf", "Bandit")
+#[spacetimedb::table(name = npc_definition, public)]
+pub struct NpcDefinition {
+    #[primary_key]
+    pub npc_def_id: u32,
+    /// Display name of the NPC
+    pub name: String,
+    /// Key of the model the client renders
+    pub model_key: String,
+    /// Entity kind (Npc or Creature)
+    pub kind: DbEntityKind,
+    /// References Faction.faction_id
+    pub faction_id: u32,
+    pub max_health: f32,
+    /// Damage dealt per attack
+    pub attack_damage: f32,
+    pub damage_type: DbDamageType,
+    pub attack_range: f32,
+    /// Time in microseconds between attacks
+    pub attack_cooldown_us: i64,
+    /// Movement speed in units per second
+    pub movement_speed: f32,
+    /// Whether the NPC attacks players on sight (otherwise only when attacked)
+    pub aggressive: bool,
+    /// Distance at which the NPC notices players
+    pub aggro_radius: f32,
+    /// Maximum distance from home before the NPC gives up and returns
+    pub leash_radius: f32,
+    /// Maximum distance from home the NPC wanders while idle
+    pub wander_radius: f32,
+    /// Items given to the player who lands the killing blow
+    pub loot_table: Vec<DbLootEntry>,
+}
+
+/// A point in the world that keeps a number of NPCs alive
+#[spacetimedb::table(name = npc_spawner, public)]
+pub struct NpcSpawner {
+    #[primary_key]
+    #[auto_inc]
+    pub spawner_id: u32,
+    /// References NpcDefinition.npc_def_id
+    pub npc_def_id: u32,
+    pub position: DbVector3,
+    pub rotation: DbVector3,
+    /// Number of NPCs this spawner keeps alive
+    pub max_alive: u32,
+    /// Time in microseconds after a death before a replacement spawns
+    pub respawn_time_us: i64,
+    /// Timestamp after which the next NPC may spawn
+    pub next_spawn_at_us: i64,
+}
+
+/// Extension row for entities of kind Npc or Creature
+#[spacetimedb::table(name = npc, public)]
+pub struct Npc {
+    /// References Entity.entity_id
+    #[primary_key]
+    pub entity_id: u32,
+    /// References NpcDefinition.npc_def_id
+    pub npc_def_id: u32,
+    /// References NpcSpawner.spawner_id (0 = not spawned by a spawner)
+    #[index(btree)]
+    pub spawner_id: u32,
+    pub state: DbNpcState,
+    /// Position the NPC wanders around and returns to
+    pub home_position: DbVector3,
+    /// Entity currently being chased or attacked
+    pub target_entity_id: Option<u32>,
+    /// Destination while wandering
+    pub wander_target: Option<DbVector3>,
+    /// Timestamp when the NPC entered its current state
+    pub state_changed_at_us: i64,
+    /// How long to stay idle before wandering
+    pub idle_duration_us: i64,
+    /// Timestamp of the last attack
+    pub last_attack_us: i64,
+    /// Timestamp of the last brain tick
+    pub last_tick_us: i64,
+}
+
+/// Schedule driving `npc_ai_tick`
+#[spacetimedb::table(name = npc_ai_schedule, scheduled(npc_ai_tick))]
+pub struct NpcAiSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Initialize default NPC definitions and start the AI schedule
+pub fn npc_definition_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Wolf - npc_def_id 0
+    ctx.db.npc_definition().insert(NpcDefinition {
+        npc_def_id: 0,
+        name: "Wolf".to_string(),
+        model_key: "creature_wolf".to_string(),
+        kind: DbEntityKind::Creature,
+        faction_id: 3, // Wildlife
+        max_health: 60.0,
+        attack_damage: 8.0,
+        damage_type: DbDamageType::Pierce,
+        attack_range: 2.0,
+        attack_cooldown_us: 1_500_000, // 1.5 seconds
+        movement_speed: 5.0,
+        aggressive: true,
+        aggro_radius: 10.0,
+        leash_radius: 25.0,
+        wander_radius: 8.0,
+        loot_table: vec![DbLootEntry {
+            item_id: 4, // Hide
+            min_quantity: 1,
+            max_quantity: 2,
+            chance: 1.0,
+        }],
+    });
+
+    // Bandit - npc_def_id 1
+    ctx.db.npc_definition().insert(NpcDefinition {
+        npc_def_id: 1,
+        name: "Bandit".to_string(),
+        model_key: "npc_bandit".to_string(),
+        kind: DbEntityKind::Npc,
+        faction_id: 2, // Bandits
+        max_health: 100.0,
+        attack_damage: 12.0,
+        damage_type: DbDamageType::Slash,
+        attack_range: 2.5,
+        attack_cooldown_us: 2_000_000, // 2 seconds
+        movement_speed: 4.5,
+        aggressive: true,
+        aggro_radius: 12.0,
+        leash_radius: 30.0,
+        wander_radius: 6.0,
+        loot_table: vec![
+            DbLootEntry {
+                item_id: 3, // Arrow
+                min_quantity: 2,
+                max_quantity: 6,
+                chance: 0.5,
+            },
+            DbLootEntry {
+                item_id: 1, // Rock
+                min_quantity: 1,
+                max_quantity: 3,
+                chance: 0.75,
+            },
+        ],
+    });
+
+    // Deer - npc_def_id 2
+    ctx.db.npc_definition().insert(NpcDefinition {
+        npc_def_id: 2,
+        name: "Deer".to_string(),
+        model_key: "creature_deer".to_string(),
+        kind: DbEntityKind::Creature,
+        faction_id: 3, // Wildlife
+        max_health: 40.0,
+        attack_damage: 5.0,
+        damage_type: DbDamageType::Blunt,
+        attack_range: 2.0,
+        attack_cooldown_us: 2_000_000, // 2 seconds
+        movement_speed: 6.0,
+        aggressive: false,
+        aggro_radius: 0.0,
+        leash_radius: 20.0,
+        wander_radius: 12.0,
+        loot_table: vec![DbLootEntry {
+            item_id: 4, // Hide
+            min_quantity: 1,
+            max_quantity: 1,
+            chance: 1.0,
+        }],
+    });
+
+    // Bandit Chief - npc_def_id 3 (boss, see boss_definition)
+    ctx.db.npc_definition().insert(NpcDefinition {
+        npc_def_id: 3,
+        name: "Bandit Chief".to_string(),
+        model_key: "npc_bandit_chief".to_string(),
+        kind: DbEntityKind::Npc,
+        faction_id: 2, // Bandits
+        max_health: 800.0,
+        attack_damage: 18.0,
+        damage_type: DbDamageType::Slash,
+        attack_range: 3.0,
+        attack_cooldown_us: 2_500_000, // 2.5 seconds
+        movement_speed: 4.0,
+        aggressive: true,
+        aggro_radius: 15.0,
+        leash_radius: 35.0,
+        wander_radius: 0.0,
+        loot_table: Vec::new(),
+    });
+
+    log::info!("Initialized default NPC definitions");
+
+    ctx.db.npc_ai_schedule().insert(NpcAiSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(NPC_AI_TICK_INTERVAL_US).into(),
+    });
+
+    Ok(())
+}
+
+/// Creates a new NPC definition (admin only)
+#[spacetimedb::reducer]
+#[allow(clippy::too_many_arguments)]
+pub fn npc_create_definition(
+    ctx: &ReducerContext,
+    npc_def_id: u32,
+    name: String,
+    model_key: String,
+    kind: DbEntityKind,
+    faction_id: u32,
+    max_health: f32,
+    attack_damage: f32,
+    damage_type: DbDamageType,
+    attack_range: f32,
+    attack_cooldown_seconds: f32,
+    movement_speed: f32,
+    aggressive: bool,
+    aggro_radius: f32,
+    leash_radius: f32,
+    wander_radius: f32,
+    loot_table: Vec<DbLootEntry>,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if !matches!(kind, DbEntityKind::Npc | DbEntityKind::Creature) {
+        return Err("NPC kind must be Npc or Creature".to_string());
+    }
+    item_definition_check_all(ctx, loot_table.iter().map(|entry| entry.item_id))?;
+
+    if ctx
+        .db
+        .npc_definition()
+        .npc_def_id()
+        .find(npc_def_id)
+        .is_some()
+    {
+        return Err(format!("NPC definition {} already exists", npc_def_id));
+    }
+
+    ctx.db.npc_definition().insert(NpcDefinition {
+        npc_def_id,
+        name,
+        model_key,
+        kind,
+        faction_id,
+        max_health,
+        attack_damage,
+        damage_type,
+        attack_range,
+        attack_cooldown_us: (attack_cooldown_seconds * 1_000_000.0) as i64,
+        movement_speed,
+        aggressive,
+        aggro_radius,
+        leash_radius,
+        wander_radius,
+        loot_table,
+    });
+    log::info!("Created NPC definition with npc_def_id: {}", npc_def_id);
+    Ok(())
+}
+
+/// Creates a spawner that keeps NPCs of a definition alive (admin only)
+#[spacetimedb::reducer]
+pub fn npc_create_spawner(
+    ctx: &ReducerContext,
+    npc_def_id: u32,
+    position: DbVector3,
+    rotation: DbVector3,
+    max_alive: u32,
+    respawn_time_seconds: f32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx
+        .db
+        .npc_definition()
+        .npc_def_id()
+        .find(npc_def_id)
+        .is_none()
+    {
+        return Err(format!("NPC definition {} does not exist", npc_def_id));
+    }
+
+    ctx.db.npc_spawner().insert(NpcSpawner {
+        spawner_id: 0,
+        npc_def_id,
+        position,
+        rotation,
+        max_alive,
+        respawn_time_us: (respawn_time_seconds * 1_000_000.0) as i64,
+        next_spawn_at_us: 0,
+    });
+    log::info!("Created NPC spawner for npc_def_id: {}", npc_def_id);
+    Ok(())
+}
+
+/// Delete a spawner and every NPC it spawned (admin only)
+#[spacetimedb::reducer]
+pub fn npc_delete_spawner(ctx: &ReducerContext, spawner_id: u32) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx.db.npc_spawner().spawner_id().find(spawner_id).is_none() {
+        return Err(format!("NPC spawner {} does not exist", spawner_id));
+    }
+
+    let spawned: Vec<_> = ctx
+        .db
+        .npc()
+        .spawner_id()
+        .filter(spawner_id)
+        .map(|npc| npc.entity_id)
+        .collect();
+    for entity_id in &spawned {
+        threat_clear_npc(ctx, *entity_id);
+        ctx.db.npc().entity_id().delete(*entity_id);
+        entity_delete(ctx, *entity_id);
+    }
+
+    ctx.db.npc_spawner().spawner_id().delete(spawner_id);
+    log::info!(
+        "Deleted NPC spawner {} and {} NPCs",
+        spawner_id,
+        spawned.len()
+    );
+    Ok(())
+}
+
+/// Internal function for spawning an NPC at a position
+pub fn npc_spawn(
+    ctx: &ReducerContext,
+    npc_def_id: u32,
+    position: DbVector3,
+    rotation: DbVector3,
+    spawner_id: u32,
+) -> Result<Npc, String> {
+    let definition = ctx
+        .db
+        .npc_definition()
+        .npc_def_id()
+        .find(npc_def_id)
+        .ok_or("NPC definition not found")?;
+
+    let mut entity = entity_create(
+        ctx,
+        definition.kind,
+        position.clone(),
+        rotation,
+        definition.max_health,
+    )?;
+    entity.attack_range = definition.attack_range;
+    entity.attack_damage = definition.attack_damage;
+    entity.faction_id = definition.faction_id;
+    let entity = ctx.db.entity().entity_id().update(entity);
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let npc = ctx.db.npc().insert(Npc {
+        entity_id: entity.entity_id,
+        npc_def_id,
+        spawner_id,
+        state: DbNpcState::Idle,
+        home_position: position,
+        target_entity_id: None,
+        wander_target: None,
+        state_changed_at_us: current_time,
+        idle_duration_us: ctx.rng().gen_range(IDLE_MIN_US..=IDLE_MAX_US),
+        last_attack_us: 0,
+        last_tick_us: current_time,
+    });
+
+    log::info!("Spawned {} as entity {}", definition.name, entity.entity_id);
+    Ok(npc)
+}
+
+/// Called when an NPC takes damage so the attacker builds threat
+/// NPCs walking home ignore damage until they arrive
+pub fn npc_on_damaged(ctx: &ReducerContext, entity_id: u32, attacker_entity_id: u32, damage: f32) {
+    if let Some(npc) = ctx.db.npc().entity_id().find(entity_id) {
+        if npc.state != DbNpcState::Return {
+            threat_on_damage(ctx, entity_id, attacker_entity_id, damage);
+        }
+    }
+}
+
+/// Called when an NPC dies: rolls loot for the killer and frees the spawner slot
+pub fn npc_on_killed(ctx: &ReducerContext, entity_id: u32, killer_entity_id: u32) {
+    let npc = match ctx.db.npc().entity_id().find(entity_id) {
+        Some(npc) => npc,
+        None => return,
+    };
+
+    if let Some(definition) = ctx.db.npc_definition().npc_def_id().find(npc.npc_def_id) {
+        if let Some(killer) = ctx.db.player().entity_id().find(killer_entity_id) {
+            for loot in npc_roll_loot(ctx, &definition.loot_table) {
+                match inventory_add_item_partial(ctx, killer.identity, loot.id, loot.quantity) {
+                    Ok(overflow) => {
+                        if overflow < loot.quantity {
+                            let added = loot.quantity - overflow;
+                            player_stats_on_loot(ctx, killer.identity, loot.id, added);
+                        }
+                        if overflow > 0 {
+                            log::warn!(
+                                "Inventory of {:?} is full, {} of item {} was lost",
+                                killer.identity,
+                                overflow,
+                                loot.id
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to give loot to {:?}: {}", killer.identity, err)
+                    }
+                }
+            }
+        }
+    }
+
+    boss_on_killed(ctx, entity_id);
+
+    if let Some(mut spawner) = ctx.db.npc_spawner().spawner_id().find(npc.spawner_id) {
+        spawner.next_spawn_at_us =
+            ctx.timestamp.to_micros_since_unix_epoch() + spawner.respawn_time_us;
+        ctx.db.npc_spawner().spawner_id().update(spawner);
+    }
+
+    threat_clear_npc(ctx, entity_id);
+    ctx.db.npc().entity_id().delete(entity_id);
+    entity_delete(ctx, entity_id);
+}
+
+/// Roll each entry of a loot table
+pub fn npc_roll_loot(ctx: &ReducerContext, loot_table: &[DbLootEntry]) -> Vec<ItemRef> {
+    let mut rng = ctx.rng();
+    loot_table
+        .iter()
+        .filter_map(|entry| {
+            if rng.gen::<f32>() >= entry.chance {
+                return None;
+            }
+            let quantity =
+                rng.gen_range(entry.min_quantity..=entry.max_quantity.max(entry.min_quantity));
+            (quantity > 0).then_some(ItemRef::new(entry.item_id, quantity))
+        })
+        .collect()
+}
+
+/// Give up the fight: forget all threat and walk home
+fn npc_leash(ctx: &ReducerContext, npc: &mut Npc) {
+    npc.target_entity_id = None;
+    threat_clear_npc(ctx, npc.entity_id);
+    boss_on_leash(ctx, npc.entity_id);
+    npc_set_state(ctx, npc, DbNpcState::Return);
+}
+
+fn npc_set_state(ctx: &ReducerContext, npc: &mut Npc, state: DbNpcState) {
+    if npc.state != state {
+        npc.state = state;
+        npc.state_changed_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    }
+}
+
+/// Step an NPC entity towards a goal, staying on the NavMesh
+/// Returns false if the step would leave the walkable surface
+fn npc_move_towards(
+    ctx: &ReducerContext,
+    entity: &mut Entity,
+    goal: &DbVector3,
+    distance: f32,
+) -> bool {
+    let to_goal_x = goal.x - entity.position.x;
+    let to_goal_z = goal.z - entity.position.z;
+    let remaining = (to_goal_x.powi(2) + to_goal_z.powi(2)).sqrt();
+    if remaining <= f32::EPSILON {
+        return true;
+    }
+
+    let step = distance.min(remaining) / remaining;
+    let x = entity.position.x + to_goal_x * step;
+    let z = entity.position.z + to_goal_z * step;
+    let y = navmesh_surface_height(ctx, x, z).unwrap_or(entity.position.y);
+
+    if !is_position_valid(ctx, x, y, z) {
+        return false;
+    }
+
+    entity.position = DbVector3 { x, y, z };
+    entity.rotation.y = to_goal_x.atan2(to_goal_z).to_degrees();
+    true
+}
+
+/// Find the closest living hostile player within a radius that the NPC can see
+fn npc_find_player_target(ctx: &ReducerContext, entity: &Entity, radius: f32) -> Option<u32> {
+    entity_find_in_radius(ctx, &entity.position, radius, Some(DbEntityKind::Player))
+        .into_iter()
+        .filter(|candidate| candidate.health > 0.0)
+        .filter(|candidate| {
+            faction_relationship_between(ctx, entity.faction_id, candidate.faction_id)
+                == DbFactionRelationship::Hostile
+        })
+        .filter(|candidate| {
+            ctx.db
+                .player()
+                .entity_id()
+                .find(candidate.entity_id)
+                .is_some_and(|player| player.online)
+        })
+        .filter(|candidate| has_line_of_sight(ctx, &entity.position, &candidate.position, None))
+        .min_by(|a, b| {
+            a.position
+                .distance(&entity.position)
+                .total_cmp(&b.position.distance(&entity.position))
+        })
+        .map(|candidate| candidate.entity_id)
+}
+
+/// Run one step of an NPC's state machine
+fn npc_think(ctx: &ReducerContext, npc: &mut Npc, entity: &mut Entity, definition: &NpcDefinition) {
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let delta_secs = (current_time - npc.last_tick_us) as f32 / 1_000_000.0;
+    let step =
+        definition.movement_speed * zone_speed_multiplier_at(ctx, &entity.position) * delta_secs;
+    npc.last_tick_us = current_time;
+
+    // Too far from home - drop everything and go back
+    if npc.state != DbNpcState::Return
+        && entity.position.distance(&npc.home_position) > definition.leash_radius
+    {
+        npc_leash(ctx, npc);
+    }
+
+    // Aggressive NPCs notice players while not busy
+    if definition.aggressive && matches!(npc.state, DbNpcState::Idle | DbNpcState::Wander) {
+        if let Some(target_id) = npc_find_player_target(ctx, entity, definition.aggro_radius) {
+            threat_add(ctx, npc.entity_id, target_id, AGGRO_THREAT);
+        }
+    }
+
+    // Fight whoever has the most threat within the leash radius
+    let target = if npc.state == DbNpcState::Return {
+        None
+    } else {
+        threat_top_target(
+            ctx,
+            npc.entity_id,
+            &npc.home_position,
+            definition.leash_radius,
+        )
+        .and_then(|target_id| ctx.db.entity().entity_id().find(target_id))
+    };
+    npc.target_entity_id = target.as_ref().map(|target| target.entity_id);
+
+    if target.is_some() && matches!(npc.state, DbNpcState::Idle | DbNpcState::Wander) {
+        npc.wander_target = None;
+        npc_set_state(ctx, npc, DbNpcState::Chase);
+    }
+
+    match (npc.state, target) {
+        (DbNpcState::Return, _) => {
+            let mut arrived = entity.position.distance(&npc.home_position) <= ARRIVAL_DISTANCE;
+            if !arrived && !npc_move_towards(ctx, entity, &npc.home_position.clone(), step) {
+                // Stuck on the way back - put it home so it is never healed away from home
+                entity.position = npc.home_position.clone();
+                arrived = true;
+            }
+            if arrived {
+                // Back home - recover fully
+                entity.health = entity.max_health;
+                npc_set_state(ctx, npc, DbNpcState::Idle);
+            }
+        }
+        (DbNpcState::Chase | DbNpcState::Attack, Some(target)) => {
+            let in_range = entity.position.distance(&target.position) <= definition.attack_range
+                && has_line_of_sight(ctx, &entity.position, &target.position, None);
+
+            if !in_range {
+                npc_set_state(ctx, npc, DbNpcState::Chase);
+                if !npc_move_towards(ctx, entity, &target.position, step) {
+                    npc_leash(ctx, npc);
+                }
+                return;
+            }
+
+            npc_set_state(ctx, npc, DbNpcState::Attack);
+            entity.rotation.y = (target.position.x - entity.position.x)
+                .atan2(target.position.z - entity.position.z)
+                .to_degrees();
+
+            if current_time - npc.last_attack_us >= definition.attack_cooldown_us
+                && !is_staggered(entity, current_time)
+            {
+                npc.last_attack_us = current_time;
+                if let Err(err) = entity_apply_damage_internal(
+                    ctx,
+                    entity.entity_id,
+                    target.entity_id,
+                    definition.attack_damage,
+                    definition.damage_type,
+                ) {
+                    log::warn!(
+                        "NPC {} failed to attack entity {}: {}",
+                        entity.entity_id,
+                        target.entity_id,
+                        err
+                    );
+                }
+            }
+        }
+        (DbNpcState::Chase | DbNpcState::Attack, None) => {
+            npc_leash(ctx, npc);
+        }
+        (DbNpcState::Idle, _) => {
+            if current_time - npc.state_changed_at_us >= npc.idle_duration_us
+                && definition.wander_radius > 0.0
+            {
+                let angle = ctx.rng().gen_range(0.0..std::f32::consts::TAU);
+                let distance = ctx.rng().gen_range(0.0..definition.wander_radius);
+                npc.wander_target = Some(DbVector3 {
+                    x: npc.home_position.x + angle.sin() * distance,
+                    y: npc.home_position.y,
+                    z: npc.home_position.z + angle.cos() * distance,
+                });
+                npc_set_state(ctx, npc, DbNpcState::Wander);
+            }
+        }
+        (DbNpcState::Wander, _) => {
+            let arrived = match &npc.wander_target {
+                Some(goal) => {
+                    let goal = goal.clone();
+                    let horizontal = ((goal.x - entity.position.x).powi(2)
+                        + (goal.z - entity.position.z).powi(2))
+                    .sqrt();
+                    horizontal <= ARRIVAL_DISTANCE || !npc_move_towards(ctx, entity, &goal, step)
+                }
+                None => true,
+            };
+            if arrived {
+                npc.wander_target = None;
+                npc.idle_duration_us = ctx.rng().gen_range(IDLE_MIN_US..=IDLE_MAX_US);
+                npc_set_state(ctx, npc, DbNpcState::Idle);
+            }
+        }
+    }
+}
+
+/// Spawn NPCs for spawners below capacity and run every NPC's state machine
+#[spacetimedb::reducer]
+pub fn npc_ai_tick(ctx: &ReducerContext, _schedule: NpcAiSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `npc_ai_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+
+    // Top up spawners, one NPC per spawner per tick
+    let spawners: Vec<_> = ctx.db.npc_spawner().iter().collect();
+    for spawner in spawners {
+        let alive = ctx.db.npc().spawner_id().filter(spawner.spawner_id).count() as u32;
+        if alive < spawner.max_alive && current_time >= spawner.next_spawn_at_us {
+            if let Err(err) = npc_spawn(
+                ctx,
+                spawner.npc_def_id,
+                spawner.position.clone(),
+                spawner.rotation.clone(),
+                spawner.spawner_id,
+            ) {
+                log::warn!("Spawner {} failed to spawn: {}", spawner.spawner_id, err);
+            }
+        }
+    }
+
+    let npcs: Vec<_> = ctx.db.npc().iter().collect();
+    for mut npc in npcs {
+        let entity = ctx.db.entity().entity_id().find(npc.entity_id);
+        let definition = ctx.db.npc_definition().npc_def_id().find(npc.npc_def_id);
+        let (mut entity, definition) = match (entity, definition) {
+            (Some(entity), Some(definition)) => (entity, definition),
+            _ => {
+                // Entity or definition no longer exists
+                ctx.db.npc().entity_id().delete(npc.entity_id);
+                continue;
+            }
+        };
+
+        // Dead NPCs and NPCs being knocked back don't think
+        if entity.health <= 0.0 || impulse_is_active(ctx, npc.entity_id) {
+            continue;
+        }
+
+        npc_think(ctx, &mut npc, &mut entity, &definition);
+
+        // Attacks may have changed the entity (e.g. a parry stagger), so keep those
+        // fields from the stored row and only write back what the brain controls
+        if let Some(mut stored) = ctx.db.entity().entity_id().find(npc.entity_id) {
+            stored.position = entity.position;
+            stored.rotation = entity.rotation;
+            stored.health = entity.health;
+            ctx.db.entity().entity_id().update(stored);
+        }
+        ctx.db.npc().entity_id().update(npc);
+    }
+
+    Ok(())
+}
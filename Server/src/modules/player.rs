@@ -1,7 +1,18 @@
 use crate::modules::creative_camera::{creative_camera_create, creative_camera_set_enabled};
-use crate::modules::entity::{entity, entity_create};
+use crate::modules::encumbrance::{encumbrance_tier, DEFAULT_CARRY_CAPACITY};
+use crate::modules::entity::{entity, entity_create, DbEntityKind};
+use crate::modules::equipment::equipment_create;
+use crate::modules::impulse::{entity_impulse, impulse_is_active};
 use crate::modules::inventory::inventory_create;
 use crate::modules::navmesh::is_position_valid;
+use crate::modules::player_stats::player_stats_flush_distance;
+use crate::modules::stamina::{stamina_spend, SPRINT_STAMINA_PER_SEC};
+use crate::modules::status_effect::{
+    status_effect_clear_entity, status_effect_movement_speed_multiplier,
+};
+use crate::modules::threat::threat_clear_source;
+use crate::modules::world_spawn::world_spawn;
+use crate::modules::zone::zone_speed_multiplier_at;
 use crate::types::{DbVector2, DbVector3};
 use spacetimedb::{Identity, ReducerContext, SpacetimeType, Table};
 
@@ -23,6 +34,8 @@ pub struct Player {
     #[unique]
     #[auto_inc]
     pub player_id: u32,
+    /// References Entity.entity_id (kind Player)
+    #[unique]
     pub entity_id: u32,
     #[index(btree)]
     pub online: bool,
@@ -34,14 +47,35 @@ pub struct Player {
     pub last_update_timestamp: i64,
     /// Maximum allowed movement speed in units per second
     pub movement_speed: f32,
+    /// Maximum allowed movement speed while sprinting (drains stamina)
+    pub sprint_speed: f32,
+    /// Total item weight carried before becoming overloaded
+    pub carry_capacity: f32,
     /// Maximum distance for interacting with lootables
     pub interaction_range: f32,
     /// Safety margin for client-side movement reconciliation (in meters)
     pub reconciliation_safety_margin: f32,
+    /// Whether the player has opted in to PvP
+    pub pvp_enabled: bool,
+    /// Timestamp when the PvP flag was last toggled (0 = never)
+    pub pvp_toggled_at_us: i64,
+    /// Incremented whenever the server moves the player (e.g., knockback)
+    /// Clients snap to their entity's position when this changes
+    pub position_correction_seq: u32,
+    /// Distance moved since it was last added to the player's stats (in meters)
+    pub unflushed_distance: f64,
+    /// Timestamp of the player's last taunt (0 = never)
+    pub taunted_at_us: i64,
 }
 
 pub fn player_create(ctx: &ReducerContext) -> Result<(), String> {
-    let entity = entity_create(ctx)?;
+    let (position, rotation) = if let Some(spawn) = ctx.db.world_spawn().id().find(0) {
+        (spawn.position, spawn.rotation)
+    } else {
+        (DbVector3::default(), DbVector3::default())
+    };
+
+    let entity = entity_create(ctx, DbEntityKind::Player, position, rotation, 100.0)?;
 
     let spawn_position = entity.position;
 
@@ -63,8 +97,15 @@ pub fn player_create(ctx: &ReducerContext) -> Result<(), String> {
         last_valid_position: spawn_position,
         last_update_timestamp: ctx.timestamp.to_micros_since_unix_epoch(),
         movement_speed: 6.0,
+        sprint_speed: 9.0,
+        carry_capacity: DEFAULT_CARRY_CAPACITY,
         interaction_range: 3.0,
         reconciliation_safety_margin: 1.5,
+        pvp_enabled: false,
+        pvp_toggled_at_us: 0,
+        position_correction_seq: 0,
+        unflushed_distance: 0.0,
+        taunted_at_us: 0,
     });
 
     log::debug!("Player {} created", ctx.sender);
@@ -80,12 +121,47 @@ pub fn player_set_online_status(ctx: &ReducerContext, online: bool) -> Result<()
             log::debug!("Player {} is offline", ctx.sender);
         }
 
+        if !online {
+            player_stats_flush_distance(ctx, &mut player);
+            // NPCs stop chasing players who left (their entity stays where they logged off)
+            threat_clear_source(ctx, player.entity_id);
+        }
         player.online = online;
         ctx.db.player().identity().update(player);
     }
     Ok(())
 }
 
+/// Move a killed player back to the world spawn with full health and stamina
+pub fn player_respawn(ctx: &ReducerContext, entity_id: u32) {
+    let (position, rotation) = if let Some(spawn) = ctx.db.world_spawn().id().find(0) {
+        (spawn.position, spawn.rotation)
+    } else {
+        (DbVector3::default(), DbVector3::default())
+    };
+
+    if let Some(mut entity) = ctx.db.entity().entity_id().find(entity_id) {
+        entity.position = position.clone();
+        entity.rotation = rotation;
+        entity.health = entity.max_health;
+        entity.stamina = entity.max_stamina;
+        entity.block_started_at_us = 0;
+        entity.staggered_until_us = 0;
+        ctx.db.entity().entity_id().update(entity);
+    }
+
+    status_effect_clear_entity(ctx, entity_id);
+    ctx.db.entity_impulse().entity_id().delete(entity_id);
+
+    if let Some(mut player) = ctx.db.player().entity_id().find(entity_id) {
+        player.last_valid_position = position;
+        player.last_update_timestamp = ctx.timestamp.to_micros_since_unix_epoch();
+        player.position_correction_seq = player.position_correction_seq.wrapping_add(1);
+        log::info!("Player {} respawned", player.identity);
+        ctx.db.player().identity().update(player);
+    }
+}
+
 #[spacetimedb::reducer]
 pub fn player_connected(ctx: &ReducerContext) -> Result<(), String> {
     if ctx.db.player().identity().find(ctx.sender).is_some() {
@@ -95,6 +171,7 @@ pub fn player_connected(ctx: &ReducerContext) -> Result<(), String> {
         player_create(ctx)?;
         creative_camera_create(ctx)?;
         inventory_create(ctx)?;
+        equipment_create(ctx)?;
     }
     Ok(())
 }
@@ -120,11 +197,17 @@ pub fn player_update(
 pub fn player_set_position(ctx: &ReducerContext, position: DbVector3) -> Result<(), String> {
     if let Some(mut player) = ctx.db.player().identity().find(ctx.sender) {
         // Get the entity to access position
-        let mut entity = match ctx.db.entity().entity_id().find(&player.entity_id) {
+        let mut entity = match ctx.db.entity().entity_id().find(player.entity_id) {
             Some(e) => e,
             None => return Err("Entity not found".to_string()),
         };
 
+        // The server is moving the player (e.g., knockback), so client movement is ignored
+        if impulse_is_active(ctx, player.entity_id) {
+            log::debug!("Ignoring position from {} during an impulse", ctx.sender);
+            return Ok(());
+        }
+
         // Validate position is on walkable surface
         if !is_position_valid(ctx, position.x, position.y, position.z) {
             log::warn!(
@@ -154,7 +237,27 @@ pub fn player_set_position(ctx: &ReducerContext, position: DbVector3) -> Result<
             let horizontal_distance =
                 ((position.x - last_pos.x).powi(2) + (position.z - last_pos.z).powi(2)).sqrt();
             let speed = horizontal_distance / time_delta_secs;
-            let max_allowed_speed = player.movement_speed * SPEED_TOLERANCE;
+            // Slows, speed boosts, zones and encumbrance scale the player's walk and sprint speeds
+            // (the more lenient zone of the start and end points is used at zone borders)
+            let zone_multiplier = zone_speed_multiplier_at(ctx, last_pos)
+                .max(zone_speed_multiplier_at(ctx, &position));
+            let speed_multiplier = status_effect_movement_speed_multiplier(ctx, player.entity_id)
+                * zone_multiplier
+                * encumbrance_tier(ctx, &player).speed_multiplier();
+            let walk_speed = player.movement_speed * speed_multiplier;
+            let sprint_speed = player.sprint_speed * speed_multiplier;
+
+            // Moving faster than walking is only allowed while stamina lasts
+            let is_sprinting = speed > walk_speed * SPEED_TOLERANCE
+                && speed <= sprint_speed * SPEED_TOLERANCE
+                && stamina_spend(
+                    &mut entity,
+                    SPRINT_STAMINA_PER_SEC * time_delta_secs,
+                    ctx.timestamp.to_micros_since_unix_epoch(),
+                )
+                .is_ok();
+            let movement_speed = if is_sprinting { sprint_speed } else { walk_speed };
+            let max_allowed_speed = movement_speed * SPEED_TOLERANCE;
 
             if speed > max_allowed_speed {
                 log::warn!(
@@ -162,7 +265,7 @@ pub fn player_set_position(ctx: &ReducerContext, position: DbVector3) -> Result<
                     ctx.sender,
                     speed,
                     max_allowed_speed,
-                    player.movement_speed,
+                    movement_speed,
                     horizontal_distance,
                     time_delta_secs
                 );
@@ -177,6 +280,8 @@ pub fn player_set_position(ctx: &ReducerContext, position: DbVector3) -> Result<
         }
 
         // Position is valid - update entity and player
+        // Stats are only written periodically, not on every movement update
+        player.unflushed_distance += entity.position.distance(&position) as f64;
         entity.position = position.clone();
         player.last_valid_position = position;
         player.last_update_timestamp = ctx.timestamp.to_micros_since_unix_epoch();
@@ -194,7 +299,7 @@ pub fn player_set_position(ctx: &ReducerContext, position: DbVector3) -> Result<
 pub fn player_set_rotation(ctx: &ReducerContext, rotation: DbVector3) -> Result<(), String> {
     if let Some(player) = ctx.db.player().identity().find(ctx.sender) {
         // Get the entity to access rotation
-        let mut entity = match ctx.db.entity().entity_id().find(&player.entity_id) {
+        let mut entity = match ctx.db.entity().entity_id().find(player.entity_id) {
             Some(e) => e,
             None => return Err("Entity not found".to_string()),
         };
@@ -0,0 +1,277 @@
+use crate::modules::entity::{DbEntityKind, Entity};
+use crate::modules::inventory::ItemRef;
+use crate::modules::player::{player, Player};
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// Number of players listed per stat on the leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+/// How often travelled distance is flushed and the leaderboard is rebuilt (10 seconds)
+const PLAYER_STATS_TICK_INTERVAL_US: i64 = 10_000_000;
+
+/// Lifetime statistics of a player
+#[spacetimedb::table(name = player_stats, public)]
+pub struct PlayerStats {
+    #[primary_key]
+    pub identity: Identity,
+    /// Players, NPCs and creatures killed
+    pub kills: u32,
+    pub deaths: u32,
+    pub damage_dealt: f64,
+    pub damage_taken: f64,
+    /// Total quantity looted per item (from lootables, NPCs and bosses)
+    pub items_looted: Vec<ItemRef>,
+    pub pieces_built: u32,
+    pub pieces_removed: u32,
+    /// Distance moved in accepted position updates (in meters)
+    pub distance_travelled: f64,
+}
+
+/// Stats a leaderboard can be ranked by
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbPlayerStat {
+    Kills,
+    Deaths,
+    DamageDealt,
+    DamageTaken,
+    /// Total quantity of all items looted
+    ItemsLooted,
+    PiecesBuilt,
+    PiecesRemoved,
+    DistanceTravelled,
+}
+
+/// One row of the leaderboard, rebuilt periodically by `player_stats_tick`
+#[spacetimedb::table(name = player_stats_leaderboard, public)]
+pub struct LeaderboardEntry {
+    #[primary_key]
+    #[auto_inc]
+    pub entry_id: u64,
+    pub stat: DbPlayerStat,
+    /// 1 = best
+    pub rank: u32,
+    pub identity: Identity,
+    pub value: f64,
+}
+
+/// Schedule driving `player_stats_tick`
+#[spacetimedb::table(name = player_stats_schedule, scheduled(player_stats_tick))]
+pub struct PlayerStatsSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+impl DbPlayerStat {
+    const ALL: [DbPlayerStat; 8] = [
+        DbPlayerStat::Kills,
+        DbPlayerStat::Deaths,
+        DbPlayerStat::DamageDealt,
+        DbPlayerStat::DamageTaken,
+        DbPlayerStat::ItemsLooted,
+        DbPlayerStat::PiecesBuilt,
+        DbPlayerStat::PiecesRemoved,
+        DbPlayerStat::DistanceTravelled,
+    ];
+}
+
+impl PlayerStats {
+    fn new(identity: Identity) -> Self {
+        Self {
+            identity,
+            kills: 0,
+            deaths: 0,
+            damage_dealt: 0.0,
+            damage_taken: 0.0,
+            items_looted: Vec::new(),
+            pieces_built: 0,
+            pieces_removed: 0,
+            distance_travelled: 0.0,
+        }
+    }
+
+    /// Value of a stat as a number for ranking
+    pub fn get(&self, stat: DbPlayerStat) -> f64 {
+        match stat {
+            DbPlayerStat::Kills => self.kills as f64,
+            DbPlayerStat::Deaths => self.deaths as f64,
+            DbPlayerStat::DamageDealt => self.damage_dealt,
+            DbPlayerStat::DamageTaken => self.damage_taken,
+            DbPlayerStat::ItemsLooted => self
+                .items_looted
+                .iter()
+                .map(|item| item.quantity as f64)
+                .sum(),
+            DbPlayerStat::PiecesBuilt => self.pieces_built as f64,
+            DbPlayerStat::PiecesRemoved => self.pieces_removed as f64,
+            DbPlayerStat::DistanceTravelled => self.distance_travelled,
+        }
+    }
+}
+
+/// Start the player stats schedule
+pub fn player_stats_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.player_stats_schedule().insert(PlayerStatsSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(PLAYER_STATS_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+/// Apply a change to a player's stats, creating the row on first use
+pub fn player_stats_update(
+    ctx: &ReducerContext,
+    identity: Identity,
+    update: impl FnOnce(&mut PlayerStats),
+) {
+    match ctx.db.player_stats().identity().find(identity) {
+        Some(mut stats) => {
+            update(&mut stats);
+            ctx.db.player_stats().identity().update(stats);
+        }
+        None => {
+            let mut stats = PlayerStats::new(identity);
+            update(&mut stats);
+            ctx.db.player_stats().insert(stats);
+        }
+    }
+}
+
+fn player_identity(ctx: &ReducerContext, entity_id: u32) -> Option<Identity> {
+    ctx.db
+        .player()
+        .entity_id()
+        .find(entity_id)
+        .map(|player| player.identity)
+}
+
+/// Record damage dealt and taken by players
+pub fn player_stats_on_damage(
+    ctx: &ReducerContext,
+    attacker_entity_id: u32,
+    target_entity_id: u32,
+    damage: f32,
+) {
+    if let Some(identity) = player_identity(ctx, attacker_entity_id) {
+        player_stats_update(ctx, identity, |stats| stats.damage_dealt += damage as f64);
+    }
+    if let Some(identity) = player_identity(ctx, target_entity_id) {
+        player_stats_update(ctx, identity, |stats| stats.damage_taken += damage as f64);
+    }
+}
+
+/// Record a kill for the killer and a death for a killed player
+pub fn player_stats_on_kill(ctx: &ReducerContext, killer_entity_id: u32, killed: &Entity) {
+    if !matches!(
+        killed.kind,
+        DbEntityKind::Player | DbEntityKind::Npc | DbEntityKind::Creature
+    ) {
+        return;
+    }
+
+    if let Some(identity) = player_identity(ctx, killer_entity_id) {
+        player_stats_update(ctx, identity, |stats| stats.kills += 1);
+    }
+    if let Some(identity) = player_identity(ctx, killed.entity_id) {
+        player_stats_update(ctx, identity, |stats| stats.deaths += 1);
+    }
+}
+
+/// Record items a player looted
+pub fn player_stats_on_loot(ctx: &ReducerContext, identity: Identity, item_id: u32, quantity: u32) {
+    player_stats_update(ctx, identity, |stats| {
+        match stats
+            .items_looted
+            .iter_mut()
+            .find(|item| item.id == item_id)
+        {
+            Some(item) => item.quantity = item.quantity.saturating_add(quantity),
+            None => stats.items_looted.push(ItemRef::new(item_id, quantity)),
+        }
+    });
+}
+
+/// Add the distance a player moved since the last flush to their stats
+/// The caller writes the player row back
+pub fn player_stats_flush_distance(ctx: &ReducerContext, player: &mut Player) {
+    if player.unflushed_distance <= 0.0 {
+        return;
+    }
+    let distance = std::mem::take(&mut player.unflushed_distance);
+    player_stats_update(ctx, player.identity, |stats| {
+        stats.distance_travelled += distance
+    });
+}
+
+/// Top players for every stat as (stat, rank, identity, value)
+fn player_stats_rank(all_stats: &[PlayerStats]) -> Vec<(DbPlayerStat, u32, Identity, f64)> {
+    DbPlayerStat::ALL
+        .iter()
+        .flat_map(|&stat| {
+            let mut ranked: Vec<_> = all_stats
+                .iter()
+                .map(|stats| (stats.identity, stats.get(stat)))
+                .filter(|(_, value)| *value > 0.0)
+                .collect();
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+            ranked
+                .into_iter()
+                .take(LEADERBOARD_SIZE)
+                .enumerate()
+                .map(move |(index, (identity, value))| (stat, index as u32 + 1, identity, value))
+        })
+        .collect()
+}
+
+/// Flush travelled distance of online players and rebuild the leaderboard when it changed
+#[spacetimedb::reducer]
+pub fn player_stats_tick(
+    ctx: &ReducerContext,
+    _schedule: PlayerStatsSchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `player_stats_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let moved: Vec<_> = ctx
+        .db
+        .player()
+        .online()
+        .filter(true)
+        .filter(|player| player.unflushed_distance > 0.0)
+        .collect();
+    for mut player in moved {
+        player_stats_flush_distance(ctx, &mut player);
+        ctx.db.player().identity().update(player);
+    }
+
+    let all_stats: Vec<_> = ctx.db.player_stats().iter().collect();
+    let ranked = player_stats_rank(&all_stats);
+
+    let current: Vec<_> = ctx.db.player_stats_leaderboard().iter().collect();
+    let unchanged = current.len() == ranked.len()
+        && current
+            .iter()
+            .all(|entry| ranked.contains(&(entry.stat, entry.rank, entry.identity, entry.value)));
+    if unchanged {
+        return Ok(());
+    }
+
+    for entry in current {
+        ctx.db
+            .player_stats_leaderboard()
+            .entry_id()
+            .delete(entry.entry_id);
+    }
+    for (stat, rank, identity, value) in ranked {
+        ctx.db.player_stats_leaderboard().insert(LeaderboardEntry {
+            entry_id: 0,
+            stat,
+            rank,
+            identity,
+            value,
+        });
+    }
+    Ok(())
+}
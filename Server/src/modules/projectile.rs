@@ -0,0 +1,422 @@
+use crate::modules::admin::require_admin;
+use crate::modules::building_piece_placed::building_piece_placed;
+use crate::modules::combat::DbDamageType;
+use crate::modules::durability::durability_on_attack;
+use crate::modules::entity::{
+    entity, entity_apply_damage_internal, entity_create, entity_delete, entity_find_in_radius,
+    DbEntityKind,
+};
+use crate::modules::inventory::{inventory_get_item, inventory_remove_item_internal};
+use crate::modules::item_definition::item_definition_get;
+use crate::modules::line_of_sight::{
+    segment_hits_building_piece, segment_hits_terrain, EYE_HEIGHT,
+};
+use crate::modules::player::player;
+use crate::modules::stamina::{stamina_consume, ATTACK_STAMINA_COST};
+use crate::types::DbVector3;
+use spacetimedb::{ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often projectiles are advanced (50ms)
+const PROJECTILE_TICK_INTERVAL_US: i64 = 50_000;
+/// Gravitational acceleration in units per second squared
+const GRAVITY: f32 = 9.81;
+/// Maximum distance between the player's position and a projectile's launch origin
+const MAX_LAUNCH_OFFSET: f32 = 3.0;
+/// Height above an entity's position treated as the center of its hitbox
+const ENTITY_HIT_CENTER_HEIGHT: f32 = 1.0;
+/// Radius of an entity's hitbox
+const ENTITY_HIT_RADIUS: f32 = 0.5;
+
+/// Defines how an item behaves when launched as a projectile
+/// The item_id references ItemDefinition.id and is consumed on launch
+#[spacetimedb::table(name = projectile_type, public)]
+pub struct ProjectileType {
+    #[primary_key]
+    pub item_id: u32,
+    /// Damage dealt to an entity on impact
+    pub damage: f32,
+    /// Type of damage dealt on impact
+    pub damage_type: DbDamageType,
+    /// Maximum launch speed in units per second
+    pub max_speed: f32,
+    /// Multiplier applied to gravity (0 = flies straight)
+    pub gravity_scale: f32,
+    /// Collision radius of the projectile
+    pub radius: f32,
+    /// Time in microseconds before an airborne projectile is removed
+    pub lifetime_us: i64,
+}
+
+/// A projectile currently in flight
+#[spacetimedb::table(name = projectile, public)]
+pub struct Projectile {
+    #[primary_key]
+    #[auto_inc]
+    pub projectile_id: u32,
+    /// References Entity.entity_id (kind Projectile) holding the position
+    #[unique]
+    pub entity_id: u32,
+    /// Entity that launched the projectile (never hit by it)
+    pub owner_entity_id: u32,
+    /// References ProjectileType.item_id
+    pub item_id: u32,
+    /// Velocity in units per second
+    pub velocity: DbVector3,
+    /// Timestamp when the projectile was launched
+    pub launched_at_us: i64,
+    /// Timestamp of the last simulation step
+    pub last_tick_us: i64,
+}
+
+/// Schedule driving `projectile_tick`
+#[spacetimedb::table(name = projectile_tick_schedule, scheduled(projectile_tick))]
+pub struct ProjectileTickSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Initialize default projectile types and start the tick schedule
+pub fn projectile_type_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Rock - item_id 1
+    ctx.db.projectile_type().insert(ProjectileType {
+        item_id: 1,
+        damage: 8.0,
+        damage_type: DbDamageType::Blunt,
+        max_speed: 20.0,
+        gravity_scale: 1.0,
+        radius: 0.15,
+        lifetime_us: 5_000_000, // 5 seconds
+    });
+
+    // Arrow - item_id 3
+    ctx.db.projectile_type().insert(ProjectileType {
+        item_id: 3,
+        damage: 15.0,
+        damage_type: DbDamageType::Pierce,
+        max_speed: 40.0,
+        gravity_scale: 0.5,
+        radius: 0.05,
+        lifetime_us: 5_000_000, // 5 seconds
+    });
+
+    log::info!("Initialized default projectile types");
+
+    ctx.db
+        .projectile_tick_schedule()
+        .insert(ProjectileTickSchedule {
+            scheduled_id: 0,
+            scheduled_at: TimeDuration::from_micros(PROJECTILE_TICK_INTERVAL_US).into(),
+        });
+
+    Ok(())
+}
+
+/// Creates a new projectile type for an item (admin only)
+#[spacetimedb::reducer]
+#[allow(clippy::too_many_arguments)]
+pub fn projectile_create_type(
+    ctx: &ReducerContext,
+    item_id: u32,
+    damage: f32,
+    damage_type: DbDamageType,
+    max_speed: f32,
+    gravity_scale: f32,
+    radius: f32,
+    lifetime_seconds: f32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+    item_definition_get(ctx, item_id)?;
+
+    if ctx.db.projectile_type().item_id().find(item_id).is_some() {
+        return Err(format!(
+            "Projectile type for item {} already exists",
+            item_id
+        ));
+    }
+
+    ctx.db.projectile_type().insert(ProjectileType {
+        item_id,
+        damage,
+        damage_type,
+        max_speed,
+        gravity_scale,
+        radius,
+        lifetime_us: (lifetime_seconds * 1_000_000.0) as i64,
+    });
+    log::info!("Created projectile type for item_id: {}", item_id);
+    Ok(())
+}
+
+/// Player launches a projectile item from their inventory
+/// Server validates: origin near the player and not behind cover, item held,
+/// speed within the type's limit
+#[spacetimedb::reducer]
+pub fn projectile_launch(
+    ctx: &ReducerContext,
+    item_id: u32,
+    origin: DbVector3,
+    direction: DbVector3,
+    speed: f32,
+) -> Result<(), String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+
+    if !player.online {
+        return Err("Player is not online".to_string());
+    }
+
+    let player_entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+
+    let projectile_type = ctx
+        .db
+        .projectile_type()
+        .item_id()
+        .find(item_id)
+        .ok_or("Item cannot be launched as a projectile")?;
+
+    if !origin.is_finite() || !direction.is_finite() || !speed.is_finite() {
+        return Err("Launch values must be finite numbers".to_string());
+    }
+
+    // Check the projectile starts at the player, not somewhere else in the world
+    let offset = player_entity.position.distance(&origin);
+    if offset > MAX_LAUNCH_OFFSET {
+        return Err(format!(
+            "Launch origin too far from player. Distance: {:.1}, Max: {:.1}",
+            offset, MAX_LAUNCH_OFFSET
+        ));
+    }
+
+    // Walls and terrain between the player's eye and the origin can't be launched through
+    let eye = player_entity.position.add(&DbVector3 {
+        x: 0.0,
+        y: EYE_HEIGHT,
+        z: 0.0,
+    });
+    if segment_hits_building_piece(ctx, &eye, &origin, None).is_some()
+        || segment_hits_terrain(ctx, &eye, &origin).is_some()
+    {
+        return Err("Launch origin is behind cover".to_string());
+    }
+
+    let direction_length = direction.length();
+    if direction_length <= f32::EPSILON {
+        return Err("Launch direction must be non-zero".to_string());
+    }
+
+    if speed <= 0.0 {
+        return Err("Launch speed must be positive".to_string());
+    }
+    let speed = speed.min(projectile_type.max_speed);
+
+    // Launching costs stamina
+    stamina_consume(ctx, player.entity_id, ATTACK_STAMINA_COST)?;
+
+    // Consume the projectile item
+    let held = inventory_get_item(ctx, item_id)?;
+    if held.quantity < 1 {
+        return Err("No projectiles left to launch".to_string());
+    }
+    inventory_remove_item_internal(ctx, ctx.sender, item_id, 1)?;
+    durability_on_attack(ctx, player.entity_id);
+
+    let velocity = direction.scale(speed / direction_length);
+    let entity = entity_create(
+        ctx,
+        DbEntityKind::Projectile,
+        origin,
+        projectile_rotation(&velocity),
+        0.0,
+    )?;
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let projectile = ctx.db.projectile().insert(Projectile {
+        projectile_id: 0,
+        entity_id: entity.entity_id,
+        owner_entity_id: player.entity_id,
+        item_id,
+        velocity,
+        launched_at_us: current_time,
+        last_tick_us: current_time,
+    });
+
+    log::debug!(
+        "Entity {} launched projectile {} (item {})",
+        player.entity_id,
+        projectile.projectile_id,
+        item_id
+    );
+
+    Ok(())
+}
+
+/// Advance every projectile under gravity and resolve impacts
+#[spacetimedb::reducer]
+pub fn projectile_tick(
+    ctx: &ReducerContext,
+    _schedule: ProjectileTickSchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `projectile_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let projectiles: Vec<_> = ctx.db.projectile().iter().collect();
+
+    for mut projectile in projectiles {
+        let projectile_type = ctx.db.projectile_type().item_id().find(projectile.item_id);
+        let projectile_entity = ctx.db.entity().entity_id().find(projectile.entity_id);
+        let (projectile_type, mut projectile_entity) = match (projectile_type, projectile_entity) {
+            (Some(projectile_type), Some(projectile_entity)) => {
+                (projectile_type, projectile_entity)
+            }
+            _ => {
+                projectile_remove(ctx, &projectile);
+                continue;
+            }
+        };
+
+        // Semi-implicit Euler step
+        let delta_secs = (current_time - projectile.last_tick_us) as f32 / 1_000_000.0;
+        projectile.velocity.y -= GRAVITY * projectile_type.gravity_scale * delta_secs;
+        let start = projectile_entity.position.clone();
+        let end = start.add(&projectile.velocity.scale(delta_secs));
+
+        // Nearest damageable target along the path: a hitbox or a building piece's bounds
+        let piece_hit = segment_hits_building_piece(ctx, &start, &end, None).and_then(
+            |(piece_id, fraction)| {
+                ctx.db
+                    .building_piece_placed()
+                    .piece_id()
+                    .find(piece_id)
+                    .map(|piece| (piece.entity_id, fraction))
+            },
+        );
+        let target_hit = [
+            projectile_find_entity_hit(
+                ctx,
+                &start,
+                &end,
+                projectile_type.radius,
+                projectile.owner_entity_id,
+            ),
+            piece_hit,
+        ]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+        let terrain_hit = segment_hits_terrain(ctx, &start, &end);
+
+        match (target_hit, terrain_hit) {
+            (Some((target_entity_id, target_fraction)), terrain)
+                if terrain.is_none_or(|fraction| target_fraction <= fraction) =>
+            {
+                projectile_remove(ctx, &projectile);
+                if let Err(err) = entity_apply_damage_internal(
+                    ctx,
+                    projectile.owner_entity_id,
+                    target_entity_id,
+                    projectile_type.damage,
+                    projectile_type.damage_type,
+                ) {
+                    log::warn!(
+                        "Projectile {} failed to damage entity {}: {}",
+                        projectile.projectile_id,
+                        target_entity_id,
+                        err
+                    );
+                }
+            }
+            (_, Some(_)) => {
+                log::debug!("Projectile {} hit the terrain", projectile.projectile_id);
+                projectile_remove(ctx, &projectile);
+            }
+            _ if current_time - projectile.launched_at_us >= projectile_type.lifetime_us => {
+                projectile_remove(ctx, &projectile);
+            }
+            _ => {
+                projectile_entity.position = end;
+                projectile_entity.rotation = projectile_rotation(&projectile.velocity);
+                ctx.db.entity().entity_id().update(projectile_entity);
+
+                projectile.last_tick_us = current_time;
+                ctx.db.projectile().projectile_id().update(projectile);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a projectile and its entity
+fn projectile_remove(ctx: &ReducerContext, projectile: &Projectile) {
+    ctx.db
+        .projectile()
+        .projectile_id()
+        .delete(projectile.projectile_id);
+    entity_delete(ctx, projectile.entity_id);
+}
+
+/// Euler angles (degrees) pointing a projectile along its velocity
+fn projectile_rotation(velocity: &DbVector3) -> DbVector3 {
+    let horizontal_speed = (velocity.x.powi(2) + velocity.z.powi(2)).sqrt();
+    DbVector3 {
+        x: -velocity.y.atan2(horizontal_speed).to_degrees(),
+        y: velocity.x.atan2(velocity.z).to_degrees(),
+        z: 0.0,
+    }
+}
+
+/// Find the nearest entity (other than the owner) whose hitbox the segment passes through
+/// Returns the entity_id and the fraction along the segment of the closest approach
+fn projectile_find_entity_hit(
+    ctx: &ReducerContext,
+    start: &DbVector3,
+    end: &DbVector3,
+    projectile_radius: f32,
+    owner_entity_id: u32,
+) -> Option<(u32, f32)> {
+    let path = end.sub(start);
+    let path_length_sq = path.dot(&path);
+    let hit_radius = ENTITY_HIT_RADIUS + projectile_radius;
+
+    // Only entities whose hitbox could reach the segment's bounding sphere
+    let midpoint = start.lerp(end, 0.5);
+    let search_radius = start.distance(end) / 2.0 + hit_radius + ENTITY_HIT_CENTER_HEIGHT;
+
+    entity_find_in_radius(ctx, &midpoint, search_radius, None)
+        .into_iter()
+        .filter(|entity| {
+            // Building pieces are hit-tested against their bounds instead
+            entity.entity_id != owner_entity_id
+                && entity.kind.is_damageable()
+                && entity.kind != DbEntityKind::Structure
+        })
+        .filter_map(|entity| {
+            let center = DbVector3 {
+                x: entity.position.x,
+                y: entity.position.y + ENTITY_HIT_CENTER_HEIGHT,
+                z: entity.position.z,
+            };
+            let fraction = if path_length_sq > f32::EPSILON {
+                (center.sub(start).dot(&path) / path_length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let closest = start.lerp(end, fraction);
+
+            (closest.distance(&center) <= hit_radius).then_some((entity.entity_id, fraction))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
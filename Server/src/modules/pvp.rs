@@ -0,0 +1,203 @@
+use crate::modules::admin::require_admin;
+use crate::modules::building_piece_placed::building_piece_placed;
+use crate::modules::entity::{DbEntityKind, Entity};
+use crate::modules::player::player;
+use crate::modules::zone::{zone_pvp_rule_at, DbZonePvpRule};
+use spacetimedb::{ReducerContext, Table};
+
+/// Global PvP settings (single row with id 0)
+#[spacetimedb::table(name = pvp_config, public)]
+pub struct PvpConfig {
+    #[primary_key]
+    pub id: u32,
+    /// Whether players can damage each other at all (zones set to Enabled still allow it)
+    pub enabled: bool,
+    /// Time in microseconds a player must wait between toggling their PvP flag
+    pub toggle_cooldown_us: i64,
+}
+
+/// Initialize the global PvP settings
+pub fn pvp_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.pvp_config().insert(PvpConfig {
+        id: 0,
+        enabled: true,
+        toggle_cooldown_us: 300_000_000, // 5 minutes
+    });
+    Ok(())
+}
+
+fn pvp_get_config(ctx: &ReducerContext) -> Result<PvpConfig, String> {
+    ctx.db
+        .pvp_config()
+        .id()
+        .find(0)
+        .ok_or("PvP config not found".to_string())
+}
+
+/// Decide whether PvP damage is allowed from the zones of both sides and everyone's flags
+/// Zones can force PvP on or off; otherwise it must be globally enabled and both sides opted in
+fn pvp_rules_allow(
+    attacker_zone: DbZonePvpRule,
+    target_zone: DbZonePvpRule,
+    globally_enabled: bool,
+    attacker_flagged: bool,
+    target_flagged: bool,
+) -> Result<(), String> {
+    match (attacker_zone, target_zone) {
+        (DbZonePvpRule::Disabled, _) | (_, DbZonePvpRule::Disabled) => {
+            return Err("PvP is not allowed in this zone".to_string());
+        }
+        (DbZonePvpRule::Enabled, DbZonePvpRule::Enabled) => return Ok(()),
+        _ => {}
+    }
+
+    if !globally_enabled {
+        return Err("PvP is disabled".to_string());
+    }
+    if !attacker_flagged {
+        return Err("You have not enabled PvP".to_string());
+    }
+    if !target_flagged {
+        return Err("Target has not enabled PvP".to_string());
+    }
+    Ok(())
+}
+
+/// Check that the PvP rules allow a player to damage another player or their building pieces
+/// Damaging a building piece counts as PvP against its owner, judged at the piece's position
+pub fn pvp_check_damage(
+    ctx: &ReducerContext,
+    attacker: &Entity,
+    target: &Entity,
+) -> Result<(), String> {
+    if attacker.kind != DbEntityKind::Player {
+        return Ok(());
+    }
+    let attacker_player = match ctx.db.player().entity_id().find(attacker.entity_id) {
+        Some(player) => player,
+        None => return Ok(()),
+    };
+
+    let owner = match target.kind {
+        DbEntityKind::Player => ctx.db.player().entity_id().find(target.entity_id),
+        DbEntityKind::Structure => ctx
+            .db
+            .building_piece_placed()
+            .entity_id()
+            .find(target.entity_id)
+            .and_then(|piece| ctx.db.player().identity().find(piece.owner)),
+        _ => return Ok(()),
+    };
+    let owner = match owner {
+        Some(owner) if owner.identity != attacker_player.identity => owner,
+        // Players can always damage themselves and their own pieces
+        _ => return Ok(()),
+    };
+
+    pvp_rules_allow(
+        zone_pvp_rule_at(ctx, &attacker.position),
+        zone_pvp_rule_at(ctx, &target.position),
+        pvp_get_config(ctx)?.enabled,
+        attacker_player.pvp_enabled,
+        owner.pvp_enabled,
+    )
+}
+
+/// Opt in or out of PvP, limited by the toggle cooldown
+#[spacetimedb::reducer]
+pub fn pvp_set_enabled(ctx: &ReducerContext, enabled: bool) -> Result<(), String> {
+    let mut player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+
+    if player.pvp_enabled == enabled {
+        return Ok(());
+    }
+
+    let config = pvp_get_config(ctx)?;
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let elapsed = current_time - player.pvp_toggled_at_us;
+    if player.pvp_toggled_at_us > 0 && elapsed < config.toggle_cooldown_us {
+        let remaining_secs = (config.toggle_cooldown_us - elapsed) as f32 / 1_000_000.0;
+        return Err(format!(
+            "PvP flag on cooldown. {:.0}s remaining",
+            remaining_secs
+        ));
+    }
+
+    player.pvp_enabled = enabled;
+    player.pvp_toggled_at_us = current_time;
+    ctx.db.player().identity().update(player);
+
+    log::info!("Player {} set PvP enabled: {}", ctx.sender, enabled);
+    Ok(())
+}
+
+/// Turn PvP on or off globally (admin only)
+#[spacetimedb::reducer]
+pub fn pvp_set_global_enabled(ctx: &ReducerContext, enabled: bool) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let mut config = pvp_get_config(ctx)?;
+    config.enabled = enabled;
+    ctx.db.pvp_config().id().update(config);
+
+    log::info!("Global PvP enabled: {}", enabled);
+    Ok(())
+}
+
+/// Set how long players must wait between toggling their PvP flag (admin only)
+#[spacetimedb::reducer]
+pub fn pvp_set_toggle_cooldown(ctx: &ReducerContext, cooldown_seconds: f32) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let mut config = pvp_get_config(ctx)?;
+    config.toggle_cooldown_us = (cooldown_seconds * 1_000_000.0) as i64;
+    ctx.db.pvp_config().id().update(config);
+
+    log::info!("PvP toggle cooldown set to {}s", cooldown_seconds);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_zone_blocks_pvp_even_when_everyone_opted_in() {
+        for (attacker_zone, target_zone) in [
+            (DbZonePvpRule::Disabled, DbZonePvpRule::Inherit),
+            (DbZonePvpRule::Inherit, DbZonePvpRule::Disabled),
+            (DbZonePvpRule::Enabled, DbZonePvpRule::Disabled),
+        ] {
+            assert!(pvp_rules_allow(attacker_zone, target_zone, true, true, true).is_err());
+        }
+    }
+
+    #[test]
+    fn enabled_zones_allow_pvp_without_flags() {
+        assert_eq!(
+            pvp_rules_allow(
+                DbZonePvpRule::Enabled,
+                DbZonePvpRule::Enabled,
+                false,
+                false,
+                false
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn default_zones_need_global_pvp_and_both_flags() {
+        let rule = DbZonePvpRule::Inherit;
+        assert_eq!(pvp_rules_allow(rule, rule, true, true, true), Ok(()));
+        assert!(pvp_rules_allow(rule, rule, false, true, true).is_err());
+        assert!(pvp_rules_allow(rule, rule, true, false, true).is_err());
+        // Raiding a building piece whose owner has not opted in
+        assert!(pvp_rules_allow(rule, rule, true, true, false).is_err());
+    }
+}
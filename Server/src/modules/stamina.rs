@@ -0,0 +1,102 @@
+use crate::modules::entity::{entity, Entity};
+use spacetimedb::{ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often stamina regenerates (250ms)
+const STAMINA_REGEN_TICK_INTERVAL_US: i64 = 250_000;
+/// Time after spending stamina before it starts regenerating (1 second)
+const STAMINA_REGEN_DELAY_US: i64 = 1_000_000;
+/// Stamina drained per second while sprinting
+pub const SPRINT_STAMINA_PER_SEC: f32 = 10.0;
+/// Stamina spent per attack (melee or ranged)
+pub const ATTACK_STAMINA_COST: f32 = 10.0;
+/// Stamina spent per heavy melee attack
+pub const HEAVY_ATTACK_STAMINA_COST: f32 = 25.0;
+/// Stamina spent per dodge
+pub const DODGE_STAMINA_COST: f32 = 20.0;
+
+/// Schedule driving `stamina_regen_tick`
+#[spacetimedb::table(name = stamina_regen_schedule, scheduled(stamina_regen_tick))]
+pub struct StaminaRegenSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Start the stamina regeneration schedule
+pub fn stamina_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db
+        .stamina_regen_schedule()
+        .insert(StaminaRegenSchedule {
+            scheduled_id: 0,
+            scheduled_at: TimeDuration::from_micros(STAMINA_REGEN_TICK_INTERVAL_US).into(),
+        });
+    Ok(())
+}
+
+/// Spend stamina on an entity row the caller will write back
+/// Fails without spending anything if the entity doesn't have enough
+pub fn stamina_spend(entity: &mut Entity, amount: f32, current_time: i64) -> Result<(), String> {
+    if entity.stamina < amount {
+        return Err(format!(
+            "Not enough stamina. Required: {:.1}, Available: {:.1}",
+            amount, entity.stamina
+        ));
+    }
+
+    entity.stamina -= amount;
+    entity.last_stamina_use_us = current_time;
+    Ok(())
+}
+
+/// Internal function for spending an entity's stamina (used by actions like attacking)
+pub fn stamina_consume(ctx: &ReducerContext, entity_id: u32, amount: f32) -> Result<(), String> {
+    let mut entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(entity_id)
+        .ok_or("Entity not found")?;
+
+    stamina_spend(
+        &mut entity,
+        amount,
+        ctx.timestamp.to_micros_since_unix_epoch(),
+    )?;
+    ctx.db.entity().entity_id().update(entity);
+    Ok(())
+}
+
+/// Regenerate stamina for entities that haven't spent any recently
+#[spacetimedb::reducer]
+pub fn stamina_regen_tick(
+    ctx: &ReducerContext,
+    _schedule: StaminaRegenSchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err(
+            "Reducer `stamina_regen_tick` may only be invoked by the scheduler".to_string(),
+        );
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let tick_secs = STAMINA_REGEN_TICK_INTERVAL_US as f32 / 1_000_000.0;
+
+    let regenerating: Vec<_> = ctx
+        .db
+        .entity()
+        .iter()
+        .filter(|entity| {
+            entity.stamina < entity.max_stamina
+                && current_time - entity.last_stamina_use_us >= STAMINA_REGEN_DELAY_US
+        })
+        .collect();
+
+    for mut entity in regenerating {
+        entity.stamina =
+            (entity.stamina + entity.stamina_regen_per_sec * tick_secs).min(entity.max_stamina);
+        ctx.db.entity().entity_id().update(entity);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,581 @@
+use crate::modules::admin::require_admin;
+use crate::modules::combat::DbResistances;
+use crate::modules::entity::{entity, entity_on_killed};
+use crate::modules::threat::threat_on_healed;
+use spacetimedb::{ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// How often active status effects are processed (250ms)
+const STATUS_EFFECT_TICK_INTERVAL_US: i64 = 250_000;
+/// Longest duration a status effect definition can have (1 hour)
+const STATUS_EFFECT_MAX_DURATION_SECONDS: f32 = 3_600.0;
+
+/// How re-applying an effect that is already active on an entity behaves
+#[derive(SpacetimeType, Clone, Debug, PartialEq)]
+pub enum DbStatusEffectStacking {
+    /// Reset the remaining duration, keeping a single stack
+    Refresh,
+    /// Add a stack (up to max_stacks) and reset the remaining duration
+    Stack,
+    /// Do nothing while the effect is still active
+    Ignore,
+}
+
+/// Defines a type of status effect (e.g., "Poison", "Slow")
+#[spacetimedb::table(name = status_effect_definition, public)]
+pub struct StatusEffectDefinition {
+    #[primary_key]
+    pub effect_id: u32,
+    /// Display name of the effect
+    pub name: String,
+    /// Description of the effect
+    pub description: String,
+    /// Behaviour when applied to an entity that already has this effect
+    pub stacking: DbStatusEffectStacking,
+    /// Maximum number of stacks (only used by Stack)
+    pub max_stacks: u32,
+    /// Time in microseconds before the effect expires
+    pub duration_us: i64,
+    /// Time in microseconds between health ticks (0 disables health over time)
+    pub tick_interval_us: i64,
+    /// Health change per tick per stack (negative damages, positive heals)
+    pub health_per_tick: f32,
+    /// Movement speed multiplier per stack (1.0 = unchanged)
+    pub movement_speed_multiplier: f32,
+    /// Damage taken multiplier per stack (1.0 = unchanged)
+    pub damage_taken_multiplier: f32,
+    /// Armor added per stack (negative removes armor)
+    pub armor_modifier: f32,
+    /// Resistances added per stack (negative adds weakness)
+    pub resistance_modifiers: DbResistances,
+}
+
+/// A status effect currently applied to an entity
+#[spacetimedb::table(name = active_status_effect, public)]
+pub struct ActiveStatusEffect {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    /// References Entity.entity_id
+    #[index(btree)]
+    pub entity_id: u32,
+    /// References StatusEffectDefinition.effect_id
+    pub effect_id: u32,
+    /// Entity that applied the effect, if any
+    pub source_entity_id: Option<u32>,
+    /// Current number of stacks
+    pub stacks: u32,
+    /// Timestamp when the effect was first applied
+    pub applied_at_us: i64,
+    /// Timestamp when the effect expires
+    pub expires_at_us: i64,
+    /// Timestamp of the next health tick
+    pub next_tick_us: i64,
+}
+
+/// Schedule driving `status_effect_tick`
+#[spacetimedb::table(name = status_effect_tick_schedule, scheduled(status_effect_tick))]
+pub struct StatusEffectTickSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Initialize default status effects and start the tick schedule
+pub fn status_effect_init(ctx: &ReducerContext) -> Result<(), String> {
+    // Poison - effect_id 0
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 0,
+            name: "Poison".to_string(),
+            description: "Loses health every second.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 10_000_000, // 10 seconds
+            tick_interval_us: 1_000_000,
+            health_per_tick: -2.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Bleeding - effect_id 1
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 1,
+            name: "Bleeding".to_string(),
+            description: "Loses health rapidly. Stacks up to 5 times.".to_string(),
+            stacking: DbStatusEffectStacking::Stack,
+            max_stacks: 5,
+            duration_us: 6_000_000, // 6 seconds
+            tick_interval_us: 500_000,
+            health_per_tick: -1.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Slow - effect_id 2
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 2,
+            name: "Slow".to_string(),
+            description: "Movement speed reduced by 40%.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 4_000_000, // 4 seconds
+            tick_interval_us: 0,
+            health_per_tick: 0.0,
+            movement_speed_multiplier: 0.6,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Speed Boost - effect_id 3
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 3,
+            name: "Speed Boost".to_string(),
+            description: "Movement speed increased by 30%.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 8_000_000, // 8 seconds
+            tick_interval_us: 0,
+            health_per_tick: 0.0,
+            movement_speed_multiplier: 1.3,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Vulnerable - effect_id 4
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 4,
+            name: "Vulnerable".to_string(),
+            description: "Takes 25% more damage.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 6_000_000, // 6 seconds
+            tick_interval_us: 0,
+            health_per_tick: 0.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.25,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Regeneration - effect_id 5
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 5,
+            name: "Regeneration".to_string(),
+            description: "Restores health every second.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 10_000_000, // 10 seconds
+            tick_interval_us: 1_000_000,
+            health_per_tick: 2.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    // Fire Ward - effect_id 6
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 6,
+            name: "Fire Ward".to_string(),
+            description: "Fire damage taken reduced by 50%.".to_string(),
+            stacking: DbStatusEffectStacking::Refresh,
+            max_stacks: 1,
+            duration_us: 30_000_000, // 30 seconds
+            tick_interval_us: 0,
+            health_per_tick: 0.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: 0.0,
+            resistance_modifiers: DbResistances {
+                fire: 0.5,
+                ..Default::default()
+            },
+        });
+
+    // Sundered - effect_id 7
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id: 7,
+            name: "Sundered".to_string(),
+            description: "Armor reduced by 15. Stacks up to 3 times.".to_string(),
+            stacking: DbStatusEffectStacking::Stack,
+            max_stacks: 3,
+            duration_us: 8_000_000, // 8 seconds
+            tick_interval_us: 0,
+            health_per_tick: 0.0,
+            movement_speed_multiplier: 1.0,
+            damage_taken_multiplier: 1.0,
+            armor_modifier: -15.0,
+            resistance_modifiers: DbResistances::default(),
+        });
+
+    log::info!("Initialized default status effects");
+
+    ctx.db
+        .status_effect_tick_schedule()
+        .insert(StatusEffectTickSchedule {
+            scheduled_id: 0,
+            scheduled_at: TimeDuration::from_micros(STATUS_EFFECT_TICK_INTERVAL_US).into(),
+        });
+
+    Ok(())
+}
+
+/// Internal function for applying an effect (used by server-side logic like combat)
+/// Honors the definition's stacking rule when the effect is already active
+pub fn status_effect_apply_internal(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    effect_id: u32,
+    source_entity_id: Option<u32>,
+) -> Result<(), String> {
+    let definition = ctx
+        .db
+        .status_effect_definition()
+        .effect_id()
+        .find(effect_id)
+        .ok_or("Status effect definition not found")?;
+
+    if ctx.db.entity().entity_id().find(entity_id).is_none() {
+        return Err("Entity not found".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let expires_at_us = current_time + definition.duration_us;
+
+    let existing = ctx
+        .db
+        .active_status_effect()
+        .entity_id()
+        .filter(entity_id)
+        .find(|effect| effect.effect_id == effect_id);
+
+    if let Some(mut active) = existing {
+        match definition.stacking {
+            DbStatusEffectStacking::Refresh => {
+                active.expires_at_us = expires_at_us;
+            }
+            DbStatusEffectStacking::Stack => {
+                active.stacks = (active.stacks + 1).min(definition.max_stacks.max(1));
+                active.expires_at_us = expires_at_us;
+            }
+            DbStatusEffectStacking::Ignore => return Ok(()),
+        }
+        active.source_entity_id = source_entity_id;
+        ctx.db.active_status_effect().id().update(active);
+    } else {
+        ctx.db.active_status_effect().insert(ActiveStatusEffect {
+            id: 0,
+            entity_id,
+            effect_id,
+            source_entity_id,
+            stacks: 1,
+            applied_at_us: current_time,
+            expires_at_us,
+            next_tick_us: current_time + definition.tick_interval_us,
+        });
+    }
+
+    log::debug!(
+        "Applied status effect {} ({}) to entity {}",
+        effect_id,
+        definition.name,
+        entity_id
+    );
+
+    Ok(())
+}
+
+/// Combine a per-stack multiplier across all active effects on an entity
+fn status_effect_multiplier(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    multiplier: impl Fn(&StatusEffectDefinition) -> f32,
+) -> f32 {
+    ctx.db
+        .active_status_effect()
+        .entity_id()
+        .filter(entity_id)
+        .filter_map(|active| {
+            ctx.db
+                .status_effect_definition()
+                .effect_id()
+                .find(active.effect_id)
+                .map(|definition| multiplier(&definition).powi(active.stacks as i32))
+        })
+        .product::<f32>()
+        .max(0.0)
+}
+
+/// Movement speed multiplier from all active effects on an entity
+pub fn status_effect_movement_speed_multiplier(ctx: &ReducerContext, entity_id: u32) -> f32 {
+    status_effect_multiplier(ctx, entity_id, |definition| {
+        definition.movement_speed_multiplier
+    })
+}
+
+/// Damage taken multiplier from all active effects on an entity
+pub fn status_effect_damage_taken_multiplier(ctx: &ReducerContext, entity_id: u32) -> f32 {
+    status_effect_multiplier(ctx, entity_id, |definition| {
+        definition.damage_taken_multiplier
+    })
+}
+
+/// Sum the armor and resistance modifiers of all active effects on an entity
+pub fn status_effect_defense_modifiers(
+    ctx: &ReducerContext,
+    entity_id: u32,
+) -> (f32, DbResistances) {
+    ctx.db
+        .active_status_effect()
+        .entity_id()
+        .filter(entity_id)
+        .filter_map(|active| {
+            ctx.db
+                .status_effect_definition()
+                .effect_id()
+                .find(active.effect_id)
+                .map(|definition| (definition, active.stacks as f32))
+        })
+        .fold(
+            (0.0, DbResistances::default()),
+            |(armor, resistances), (definition, stacks)| {
+                (
+                    armor + definition.armor_modifier * stacks,
+                    resistances.add(&definition.resistance_modifiers.scale(stacks)),
+                )
+            },
+        )
+}
+
+/// Apply health over time and remove expired effects
+#[spacetimedb::reducer]
+pub fn status_effect_tick(
+    ctx: &ReducerContext,
+    _schedule: StatusEffectTickSchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err(
+            "Reducer `status_effect_tick` may only be invoked by the scheduler".to_string(),
+        );
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    let effect_ids: Vec<_> = ctx
+        .db
+        .active_status_effect()
+        .iter()
+        .map(|active| active.id)
+        .collect();
+
+    for id in effect_ids {
+        // A kill earlier in this tick may already have cleared the effect
+        let Some(mut active) = ctx.db.active_status_effect().id().find(id) else {
+            continue;
+        };
+        let definition = ctx
+            .db
+            .status_effect_definition()
+            .effect_id()
+            .find(active.effect_id);
+        let entity = ctx.db.entity().entity_id().find(active.entity_id);
+
+        let (definition, mut entity) = match (definition, entity) {
+            (Some(definition), Some(entity)) => (definition, entity),
+            _ => {
+                // Definition or entity no longer exists
+                ctx.db.active_status_effect().id().delete(active.id);
+                continue;
+            }
+        };
+
+        // Apply every health tick that has elapsed, including one landing exactly on expiry
+        if definition.tick_interval_us > 0 && definition.health_per_tick != 0.0 {
+            let mut health_change = 0.0;
+            while active.next_tick_us <= current_time && active.next_tick_us <= active.expires_at_us
+            {
+                health_change += definition.health_per_tick * active.stacks as f32;
+                active.next_tick_us += definition.tick_interval_us;
+            }
+
+            if health_change != 0.0 {
+                let previous_health = entity.health;
+                entity.health = (entity.health + health_change).clamp(0.0, entity.max_health);
+                let healed = entity.health - previous_health;
+                let entity = ctx.db.entity().entity_id().update(entity);
+
+                // Healing draws threat from NPCs fighting the healed entity
+                if let Some(healer_entity_id) = active.source_entity_id.filter(|_| healed > 0.0) {
+                    threat_on_healed(ctx, healer_entity_id, entity.entity_id, healed);
+                }
+
+                // Killing handles the body (respawn, despawn) and clears its effects
+                if previous_health > 0.0 && entity.health <= 0.0 {
+                    ctx.db.active_status_effect().id().delete(active.id);
+                    entity_on_killed(ctx, &entity, active.source_entity_id.unwrap_or(0));
+                    continue;
+                }
+            }
+        }
+
+        if current_time >= active.expires_at_us {
+            log::debug!(
+                "Status effect {} ({}) expired on entity {}",
+                active.effect_id,
+                definition.name,
+                active.entity_id
+            );
+            ctx.db.active_status_effect().id().delete(active.id);
+        } else {
+            ctx.db.active_status_effect().id().update(active);
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates a new status effect definition (admin only)
+#[spacetimedb::reducer]
+#[allow(clippy::too_many_arguments)]
+pub fn status_effect_create_definition(
+    ctx: &ReducerContext,
+    effect_id: u32,
+    name: String,
+    description: String,
+    stacking: DbStatusEffectStacking,
+    max_stacks: u32,
+    duration_seconds: f32,
+    tick_interval_seconds: f32,
+    health_per_tick: f32,
+    movement_speed_multiplier: f32,
+    damage_taken_multiplier: f32,
+    armor_modifier: f32,
+    resistance_modifiers: DbResistances,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if ctx
+        .db
+        .status_effect_definition()
+        .effect_id()
+        .find(effect_id)
+        .is_some()
+    {
+        return Err(format!("Status effect {} already exists", effect_id));
+    }
+    let values = [
+        duration_seconds,
+        tick_interval_seconds,
+        health_per_tick,
+        movement_speed_multiplier,
+        damage_taken_multiplier,
+        armor_modifier,
+    ];
+    if !values.iter().all(|value| value.is_finite()) || !resistance_modifiers.is_finite() {
+        return Err("Status effect values must be finite numbers".to_string());
+    }
+    if max_stacks == 0 {
+        return Err("Max stacks must be at least 1".to_string());
+    }
+    if duration_seconds <= 0.0 || duration_seconds > STATUS_EFFECT_MAX_DURATION_SECONDS {
+        return Err(format!(
+            "Duration must be positive and at most {} seconds",
+            STATUS_EFFECT_MAX_DURATION_SECONDS
+        ));
+    }
+    if tick_interval_seconds < 0.0 || tick_interval_seconds > duration_seconds {
+        return Err("Tick interval must be between 0 and the duration".to_string());
+    }
+    let duration_us = (duration_seconds * 1_000_000.0) as i64;
+    let tick_interval_us = (tick_interval_seconds * 1_000_000.0) as i64;
+    if health_per_tick != 0.0 && tick_interval_us == 0 {
+        return Err("Effects that change health need a positive tick interval".to_string());
+    }
+    if movement_speed_multiplier < 0.0 || damage_taken_multiplier < 0.0 {
+        return Err("Multipliers cannot be negative".to_string());
+    }
+
+    ctx.db
+        .status_effect_definition()
+        .insert(StatusEffectDefinition {
+            effect_id,
+            name,
+            description,
+            stacking,
+            max_stacks,
+            duration_us,
+            tick_interval_us,
+            health_per_tick,
+            movement_speed_multiplier,
+            damage_taken_multiplier,
+            armor_modifier,
+            resistance_modifiers,
+        });
+    log::info!(
+        "Created status effect definition with effect_id: {}",
+        effect_id
+    );
+    Ok(())
+}
+
+/// Apply a status effect to an entity (admin only)
+#[spacetimedb::reducer]
+pub fn status_effect_apply(
+    ctx: &ReducerContext,
+    entity_id: u32,
+    effect_id: u32,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+    status_effect_apply_internal(ctx, entity_id, effect_id, None)
+}
+
+/// Remove every status effect active on an entity, returning how many were removed
+pub fn status_effect_clear_entity(ctx: &ReducerContext, entity_id: u32) -> usize {
+    let effect_ids: Vec<_> = ctx
+        .db
+        .active_status_effect()
+        .entity_id()
+        .filter(entity_id)
+        .map(|active| active.id)
+        .collect();
+    for id in &effect_ids {
+        ctx.db.active_status_effect().id().delete(*id);
+    }
+    effect_ids.len()
+}
+
+/// Remove all status effects from an entity (admin only)
+#[spacetimedb::reducer]
+pub fn status_effect_clear(ctx: &ReducerContext, entity_id: u32) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    let cleared = status_effect_clear_entity(ctx, entity_id);
+
+    log::info!(
+        "Cleared {} status effects from entity {}",
+        cleared,
+        entity_id
+    );
+    Ok(())
+}
@@ -0,0 +1,314 @@
+use crate::modules::entity::entity;
+use crate::modules::line_of_sight::has_line_of_sight;
+use crate::modules::npc::npc;
+use crate::modules::player::player;
+use crate::types::DbVector3;
+use spacetimedb::{ReducerContext, ScheduleAt, Table, TimeDuration};
+
+/// How often threat decays (1 second)
+const THREAT_DECAY_TICK_INTERVAL_US: i64 = 1_000_000;
+/// Fraction of threat lost per second
+const THREAT_DECAY_PER_SEC: f32 = 0.05;
+/// Threat below this is forgotten
+const THREAT_MIN: f32 = 0.5;
+/// Threat generated per point of damage dealt
+const THREAT_PER_DAMAGE: f32 = 1.0;
+/// Threat generated per point of healing on an entity an NPC is fighting
+const THREAT_PER_HEALING: f32 = 0.5;
+/// Threat an aggressive NPC gains towards a player it spots
+pub const AGGRO_THREAT: f32 = 1.0;
+/// How far above the current top threat a taunt puts the taunter
+const TAUNT_THREAT_MULTIPLIER: f32 = 1.1;
+/// Flat threat added by a taunt (so taunting an NPC with no threat works)
+const TAUNT_THREAT: f32 = 10.0;
+/// Maximum distance a player can taunt from
+const TAUNT_RANGE: f32 = 15.0;
+/// Minimum time between a player's taunts (8 seconds)
+/// Keeps a player from re-taunting every tick to hold the top threat on every NPC
+const TAUNT_COOLDOWN_US: i64 = 8_000_000;
+
+/// How much an NPC wants to attack a given entity
+#[spacetimedb::table(name = npc_threat, public)]
+pub struct NpcThreat {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    /// References Npc.entity_id
+    #[index(btree)]
+    pub npc_entity_id: u32,
+    /// References Entity.entity_id of whoever generated the threat
+    #[index(btree)]
+    pub source_entity_id: u32,
+    pub threat: f32,
+}
+
+/// Schedule driving `threat_decay_tick`
+#[spacetimedb::table(name = threat_decay_schedule, scheduled(threat_decay_tick))]
+pub struct ThreatDecaySchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Start the threat decay schedule
+pub fn threat_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.threat_decay_schedule().insert(ThreatDecaySchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(THREAT_DECAY_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+fn threat_find(
+    ctx: &ReducerContext,
+    npc_entity_id: u32,
+    source_entity_id: u32,
+) -> Option<NpcThreat> {
+    ctx.db
+        .npc_threat()
+        .npc_entity_id()
+        .filter(npc_entity_id)
+        .find(|row| row.source_entity_id == source_entity_id)
+}
+
+/// Add threat towards a source on an NPC's threat table
+pub fn threat_add(ctx: &ReducerContext, npc_entity_id: u32, source_entity_id: u32, amount: f32) {
+    if npc_entity_id == source_entity_id || amount <= 0.0 {
+        return;
+    }
+
+    match threat_find(ctx, npc_entity_id, source_entity_id) {
+        Some(mut row) => {
+            row.threat += amount;
+            ctx.db.npc_threat().id().update(row);
+        }
+        None => {
+            ctx.db.npc_threat().insert(NpcThreat {
+                id: 0,
+                npc_entity_id,
+                source_entity_id,
+                threat: amount,
+            });
+        }
+    }
+}
+
+/// Add threat for damage dealt to an NPC
+pub fn threat_on_damage(
+    ctx: &ReducerContext,
+    npc_entity_id: u32,
+    attacker_entity_id: u32,
+    damage: f32,
+) {
+    threat_add(
+        ctx,
+        npc_entity_id,
+        attacker_entity_id,
+        damage * THREAT_PER_DAMAGE,
+    );
+}
+
+/// Add threat to a healer on every NPC that is fighting the healed entity
+pub fn threat_on_healed(
+    ctx: &ReducerContext,
+    healer_entity_id: u32,
+    healed_entity_id: u32,
+    amount: f32,
+) {
+    let engaged_npcs: Vec<_> = ctx
+        .db
+        .npc_threat()
+        .source_entity_id()
+        .filter(healed_entity_id)
+        .map(|row| row.npc_entity_id)
+        .collect();
+
+    for npc_entity_id in engaged_npcs {
+        threat_add(
+            ctx,
+            npc_entity_id,
+            healer_entity_id,
+            amount * THREAT_PER_HEALING,
+        );
+    }
+}
+
+/// Pick the living entity with the highest threat that is within the leash radius of home
+pub fn threat_top_target(
+    ctx: &ReducerContext,
+    npc_entity_id: u32,
+    home_position: &DbVector3,
+    leash_radius: f32,
+) -> Option<u32> {
+    ctx.db
+        .npc_threat()
+        .npc_entity_id()
+        .filter(npc_entity_id)
+        .filter(|row| {
+            ctx.db
+                .entity()
+                .entity_id()
+                .find(row.source_entity_id)
+                .is_some_and(|source| {
+                    source.health > 0.0 && source.position.distance(home_position) <= leash_radius
+                })
+        })
+        .max_by(|a, b| a.threat.total_cmp(&b.threat))
+        .map(|row| row.source_entity_id)
+}
+
+/// Forget everything on an NPC's threat table (e.g., when it leashes or dies)
+pub fn threat_clear_npc(ctx: &ReducerContext, npc_entity_id: u32) {
+    let ids: Vec<_> = ctx
+        .db
+        .npc_threat()
+        .npc_entity_id()
+        .filter(npc_entity_id)
+        .map(|row| row.id)
+        .collect();
+    for id in ids {
+        ctx.db.npc_threat().id().delete(id);
+    }
+}
+
+/// Remove an entity from every NPC's threat table (e.g., when it dies)
+pub fn threat_clear_source(ctx: &ReducerContext, source_entity_id: u32) {
+    let ids: Vec<_> = ctx
+        .db
+        .npc_threat()
+        .source_entity_id()
+        .filter(source_entity_id)
+        .map(|row| row.id)
+        .collect();
+    for id in ids {
+        ctx.db.npc_threat().id().delete(id);
+    }
+}
+
+/// Fail if a player taunted too recently to taunt again
+fn taunt_can_start(taunted_at_us: i64, current_time: i64) -> Result<(), String> {
+    if taunted_at_us > 0 && current_time - taunted_at_us < TAUNT_COOLDOWN_US {
+        return Err("Taunt is on cooldown".to_string());
+    }
+    Ok(())
+}
+
+/// Taunt an NPC, making the caller its highest threat
+#[spacetimedb::reducer]
+pub fn npc_taunt(ctx: &ReducerContext, npc_entity_id: u32) -> Result<(), String> {
+    let mut player = ctx
+        .db
+        .player()
+        .identity()
+        .find(ctx.sender)
+        .ok_or("Player not found")?;
+
+    if !player.online {
+        return Err("Player is not online".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+    taunt_can_start(player.taunted_at_us, current_time)?;
+
+    if ctx.db.npc().entity_id().find(npc_entity_id).is_none() {
+        return Err("NPC not found".to_string());
+    }
+
+    let player_entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found")?;
+    let npc_entity = ctx
+        .db
+        .entity()
+        .entity_id()
+        .find(npc_entity_id)
+        .ok_or("NPC entity not found")?;
+
+    if player_entity.health <= 0.0 || npc_entity.health <= 0.0 {
+        return Err("Cannot taunt while dead or taunt a dead NPC".to_string());
+    }
+
+    let distance = player_entity.position.distance(&npc_entity.position);
+    if distance > TAUNT_RANGE {
+        return Err(format!(
+            "NPC out of range. Distance: {:.1}, Range: {:.1}",
+            distance, TAUNT_RANGE
+        ));
+    }
+
+    if !has_line_of_sight(ctx, &player_entity.position, &npc_entity.position, None) {
+        return Err("NPC is not in line of sight".to_string());
+    }
+
+    let top_threat = ctx
+        .db
+        .npc_threat()
+        .npc_entity_id()
+        .filter(npc_entity_id)
+        .map(|row| row.threat)
+        .fold(0.0, f32::max);
+    let own_threat =
+        threat_find(ctx, npc_entity_id, player.entity_id).map_or(0.0, |row| row.threat);
+    let taunt_threat = top_threat * TAUNT_THREAT_MULTIPLIER + TAUNT_THREAT;
+
+    threat_add(
+        ctx,
+        npc_entity_id,
+        player.entity_id,
+        taunt_threat - own_threat,
+    );
+
+    let entity_id = player.entity_id;
+    player.taunted_at_us = current_time;
+    ctx.db.player().identity().update(player);
+
+    log::info!("Entity {} taunted NPC {}", entity_id, npc_entity_id);
+    Ok(())
+}
+
+/// Decay all threat over time and forget negligible entries
+#[spacetimedb::reducer]
+pub fn threat_decay_tick(
+    ctx: &ReducerContext,
+    _schedule: ThreatDecaySchedule,
+) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `threat_decay_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let tick_secs = THREAT_DECAY_TICK_INTERVAL_US as f32 / 1_000_000.0;
+    let decay = (1.0 - THREAT_DECAY_PER_SEC * tick_secs).max(0.0);
+
+    let rows: Vec<_> = ctx.db.npc_threat().iter().collect();
+    for mut row in rows {
+        row.threat *= decay;
+        if row.threat < THREAT_MIN {
+            ctx.db.npc_threat().id().delete(row.id);
+        } else {
+            ctx.db.npc_threat().id().update(row);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_taunt_is_allowed() {
+        assert!(taunt_can_start(0, 1_000).is_ok());
+    }
+
+    #[test]
+    fn taunt_is_on_cooldown_until_it_has_elapsed() {
+        let taunted_at_us = 5_000_000;
+        assert!(taunt_can_start(taunted_at_us, taunted_at_us + TAUNT_COOLDOWN_US - 1).is_err());
+        assert!(taunt_can_start(taunted_at_us, taunted_at_us + TAUNT_COOLDOWN_US).is_ok());
+    }
+}
@@ -0,0 +1,483 @@
+use crate::modules::dropped_item::dropped_item_spawn;
+use crate::modules::entity::{entity, Entity};
+use crate::modules::inventory::{
+    inventory_add_stack_internal, inventory_add_stack_partial, inventory_remove_item_internal,
+    ItemRef,
+};
+use crate::modules::item_definition::item_definition_get;
+use crate::modules::player::player;
+use spacetimedb::{Identity, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration};
+
+/// How often trades are checked for expiry, disconnects and distance (500ms)
+const TRADE_TICK_INTERVAL_US: i64 = 500_000;
+/// Maximum distance between players to request or keep a trade open
+const TRADE_RANGE: f32 = 5.0;
+/// Time a trade request stays open before expiring (30 seconds)
+const TRADE_REQUEST_TIMEOUT_US: i64 = 30_000_000;
+
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbTradeStatus {
+    /// Waiting for the partner to accept or decline
+    Requested,
+    /// Both players can place offers and confirm
+    Open,
+    /// Offers were swapped
+    Completed,
+    /// Ended without a swap (see `cancel_reason`)
+    Cancelled,
+    /// Partner declined the request
+    Declined,
+    /// Request was not answered in time
+    Expired,
+}
+
+/// Why an open trade was cancelled
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbTradeCancelReason {
+    /// One of the players cancelled it
+    Cancelled,
+    /// One of the players went offline
+    Offline,
+    /// The players moved too far apart
+    OutOfRange,
+    /// An offer changed after a player had confirmed
+    OfferModified,
+}
+
+/// A trade between two players
+/// Offered items are held in escrow (removed from the inventory) until the trade ends
+#[spacetimedb::table(name = trade, public)]
+pub struct Trade {
+    #[primary_key]
+    #[auto_inc]
+    pub trade_id: u32,
+    #[index(btree)]
+    pub status: DbTradeStatus,
+    #[index(btree)]
+    pub initiator: Identity,
+    pub initiator_offer: Vec<ItemRef>,
+    pub initiator_confirmed: bool,
+    #[index(btree)]
+    pub partner: Identity,
+    pub partner_offer: Vec<ItemRef>,
+    pub partner_confirmed: bool,
+    pub requested_at_us: i64,
+    /// Timestamp when the trade ended or the request was answered (0 = ongoing)
+    pub ended_at_us: i64,
+    pub cancel_reason: Option<DbTradeCancelReason>,
+}
+
+/// Schedule driving `trade_tick`
+#[spacetimedb::table(name = trade_schedule, scheduled(trade_tick))]
+pub struct TradeSchedule {
+    #[primary_key]
+    #[auto_inc]
+    pub scheduled_id: u64,
+    pub scheduled_at: ScheduleAt,
+}
+
+/// Start the trade schedule
+pub fn trade_init(ctx: &ReducerContext) -> Result<(), String> {
+    ctx.db.trade_schedule().insert(TradeSchedule {
+        scheduled_id: 0,
+        scheduled_at: TimeDuration::from_micros(TRADE_TICK_INTERVAL_US).into(),
+    });
+    Ok(())
+}
+
+/// Find the requested or open trade a player is part of
+fn trade_find_open(ctx: &ReducerContext, identity: Identity) -> Option<Trade> {
+    let is_open =
+        |trade: &Trade| matches!(trade.status, DbTradeStatus::Requested | DbTradeStatus::Open);
+    ctx.db
+        .trade()
+        .initiator()
+        .filter(identity)
+        .find(is_open)
+        .or_else(|| ctx.db.trade().partner().filter(identity).find(is_open))
+}
+
+/// Get an open trade the caller is part of
+fn trade_get_open(ctx: &ReducerContext, trade_id: u32) -> Result<Trade, String> {
+    let trade = ctx
+        .db
+        .trade()
+        .trade_id()
+        .find(trade_id)
+        .ok_or("Trade not found")?;
+
+    if trade.initiator != ctx.sender && trade.partner != ctx.sender {
+        return Err("You are not part of this trade".to_string());
+    }
+    if trade.status != DbTradeStatus::Open {
+        return Err("Trade is not open".to_string());
+    }
+    Ok(trade)
+}
+
+/// Get a player's entity, checking that they are online
+fn trade_get_trader(ctx: &ReducerContext, identity: Identity) -> Result<Entity, String> {
+    let player = ctx
+        .db
+        .player()
+        .identity()
+        .find(identity)
+        .ok_or("Player not found")?;
+    if !player.online {
+        return Err(format!("Player {} is not online", identity));
+    }
+    ctx.db
+        .entity()
+        .entity_id()
+        .find(player.entity_id)
+        .ok_or("Player entity not found".to_string())
+}
+
+/// Check that both traders are online and close together
+fn trade_check_traders(
+    ctx: &ReducerContext,
+    initiator: Identity,
+    partner: Identity,
+) -> Result<(), String> {
+    let initiator_entity = trade_get_trader(ctx, initiator)?;
+    let partner_entity = trade_get_trader(ctx, partner)?;
+
+    let distance = initiator_entity.position.distance(&partner_entity.position);
+    if distance > TRADE_RANGE {
+        return Err(format!(
+            "Too far away to trade. Distance: {:.1}, Range: {:.1}",
+            distance, TRADE_RANGE
+        ));
+    }
+    Ok(())
+}
+
+/// Give escrowed items back, dropping whatever no longer fits at the player's feet
+fn trade_refund(ctx: &ReducerContext, identity: Identity, items: &[ItemRef]) {
+    for item in items {
+        let overflow = match inventory_add_stack_partial(ctx, identity, item) {
+            Ok(overflow) => overflow,
+            Err(err) => {
+                log::warn!("Failed to refund trade items to {}: {}", identity, err);
+                item.quantity
+            }
+        };
+        if overflow == 0 {
+            continue;
+        }
+
+        let position = ctx
+            .db
+            .player()
+            .identity()
+            .find(identity)
+            .and_then(|player| ctx.db.entity().entity_id().find(player.entity_id))
+            .map(|entity| entity.position);
+        let dropped = position
+            .ok_or("Player entity not found".to_string())
+            .and_then(|position| {
+                dropped_item_spawn(
+                    ctx,
+                    ItemRef {
+                        quantity: overflow,
+                        ..item.clone()
+                    },
+                    position,
+                    identity,
+                )
+            });
+        if let Err(err) = dropped {
+            log::warn!(
+                "Lost {} of item {} refunded to {}: {}",
+                overflow,
+                item.id,
+                identity,
+                err
+            );
+        }
+    }
+}
+
+/// End an open trade without swapping, returning both offers
+fn trade_cancel_internal(ctx: &ReducerContext, mut trade: Trade, reason: DbTradeCancelReason) {
+    trade_refund(ctx, trade.initiator, &trade.initiator_offer);
+    trade_refund(ctx, trade.partner, &trade.partner_offer);
+
+    trade.status = DbTradeStatus::Cancelled;
+    trade.cancel_reason = Some(reason);
+    trade.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    log::info!("Trade {} cancelled ({:?})", trade.trade_id, reason);
+    ctx.db.trade().trade_id().update(trade);
+}
+
+/// Ask a nearby player to trade
+#[spacetimedb::reducer]
+pub fn trade_request(ctx: &ReducerContext, partner: Identity) -> Result<(), String> {
+    if partner == ctx.sender {
+        return Err("Cannot trade with yourself".to_string());
+    }
+
+    trade_check_traders(ctx, ctx.sender, partner)?;
+
+    if trade_find_open(ctx, ctx.sender).is_some() {
+        return Err("You already have an open trade".to_string());
+    }
+    if trade_find_open(ctx, partner).is_some() {
+        return Err("Partner already has an open trade".to_string());
+    }
+
+    let trade = ctx.db.trade().insert(Trade {
+        trade_id: 0,
+        status: DbTradeStatus::Requested,
+        initiator: ctx.sender,
+        initiator_offer: Vec::new(),
+        initiator_confirmed: false,
+        partner,
+        partner_offer: Vec::new(),
+        partner_confirmed: false,
+        requested_at_us: ctx.timestamp.to_micros_since_unix_epoch(),
+        ended_at_us: 0,
+        cancel_reason: None,
+    });
+
+    log::info!(
+        "Player {} requested trade {} with {}",
+        ctx.sender,
+        trade.trade_id,
+        partner
+    );
+    Ok(())
+}
+
+/// Find a trade request addressed to the caller that hasn't expired
+fn trade_get_pending_request(ctx: &ReducerContext, trade_id: u32) -> Result<Trade, String> {
+    let trade = ctx
+        .db
+        .trade()
+        .trade_id()
+        .find(trade_id)
+        .ok_or("Trade not found")?;
+
+    if trade.partner != ctx.sender {
+        return Err("This trade request is not addressed to you".to_string());
+    }
+    if trade.status != DbTradeStatus::Requested
+        || ctx.timestamp.to_micros_since_unix_epoch() - trade.requested_at_us
+            > TRADE_REQUEST_TIMEOUT_US
+    {
+        return Err("Trade request is no longer open".to_string());
+    }
+    Ok(trade)
+}
+
+/// Accept a trade request, opening the trade
+#[spacetimedb::reducer]
+pub fn trade_accept(ctx: &ReducerContext, trade_id: u32) -> Result<(), String> {
+    let mut trade = trade_get_pending_request(ctx, trade_id)?;
+    trade_check_traders(ctx, trade.initiator, trade.partner)?;
+
+    trade.status = DbTradeStatus::Open;
+    ctx.db.trade().trade_id().update(trade);
+
+    log::info!("Trade {} opened", trade_id);
+    Ok(())
+}
+
+/// Decline a trade request
+#[spacetimedb::reducer]
+pub fn trade_decline(ctx: &ReducerContext, trade_id: u32) -> Result<(), String> {
+    let mut trade = trade_get_pending_request(ctx, trade_id)?;
+
+    trade.status = DbTradeStatus::Declined;
+    trade.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    ctx.db.trade().trade_id().update(trade);
+
+    log::info!("Trade {} declined", trade_id);
+    Ok(())
+}
+
+/// Combine repeated items so each appears once in an offer
+fn trade_combine_offer(items: Vec<ItemRef>) -> Result<Vec<ItemRef>, String> {
+    let mut offer: Vec<ItemRef> = Vec::new();
+    for item in items {
+        if item.quantity == 0 {
+            return Err("Offered quantities must be positive".to_string());
+        }
+        match offer.iter_mut().find(|offered| offered.id == item.id) {
+            Some(offered) => {
+                offered.quantity = offered
+                    .quantity
+                    .checked_add(item.quantity)
+                    .ok_or("Offered quantity is too large")?;
+            }
+            None => offer.push(item),
+        }
+    }
+    Ok(offer)
+}
+
+/// Replace the caller's offer, moving the offered items into escrow
+/// Changing an offer after either player confirmed cancels the trade
+#[spacetimedb::reducer]
+pub fn trade_set_offer(
+    ctx: &ReducerContext,
+    trade_id: u32,
+    items: Vec<ItemRef>,
+) -> Result<(), String> {
+    let mut trade = trade_get_open(ctx, trade_id)?;
+
+    if trade.initiator_confirmed || trade.partner_confirmed {
+        trade_cancel_internal(ctx, trade, DbTradeCancelReason::OfferModified);
+        return Ok(());
+    }
+
+    let offer = trade_combine_offer(items)?;
+    for item in &offer {
+        item_definition_get(ctx, item.id)?;
+    }
+
+    // Return the previous offer before taking the new one
+    let escrow = if trade.initiator == ctx.sender {
+        &mut trade.initiator_offer
+    } else {
+        &mut trade.partner_offer
+    };
+    for item in std::mem::take(escrow) {
+        inventory_add_stack_internal(ctx, ctx.sender, &item)?;
+    }
+    // Escrow the removed items themselves so their durability is kept
+    for item in &offer {
+        escrow.extend(inventory_remove_item_internal(
+            ctx,
+            ctx.sender,
+            item.id,
+            item.quantity,
+        )?);
+    }
+
+    ctx.db.trade().trade_id().update(trade);
+    Ok(())
+}
+
+/// Confirm the current offers; the items are swapped once both players confirm
+#[spacetimedb::reducer]
+pub fn trade_confirm(ctx: &ReducerContext, trade_id: u32) -> Result<(), String> {
+    let mut trade = trade_get_open(ctx, trade_id)?;
+    trade_check_traders(ctx, trade.initiator, trade.partner)?;
+
+    if trade.initiator == ctx.sender {
+        trade.initiator_confirmed = true;
+    } else {
+        trade.partner_confirmed = true;
+    }
+
+    if !(trade.initiator_confirmed && trade.partner_confirmed) {
+        ctx.db.trade().trade_id().update(trade);
+        return Ok(());
+    }
+
+    // Swap the escrowed offers; any failure (e.g., a full inventory) undoes the whole swap
+    for item in &trade.initiator_offer {
+        inventory_add_stack_internal(ctx, trade.partner, item)
+            .map_err(|err| format!("Trade partner can't receive the items: {}", err))?;
+    }
+    for item in &trade.partner_offer {
+        inventory_add_stack_internal(ctx, trade.initiator, item)
+            .map_err(|err| format!("Trade initiator can't receive the items: {}", err))?;
+    }
+
+    trade.status = DbTradeStatus::Completed;
+    trade.ended_at_us = ctx.timestamp.to_micros_since_unix_epoch();
+    log::info!("Trade {} completed", trade_id);
+    ctx.db.trade().trade_id().update(trade);
+    Ok(())
+}
+
+/// Cancel an open trade, returning both offers
+#[spacetimedb::reducer]
+pub fn trade_cancel(ctx: &ReducerContext, trade_id: u32) -> Result<(), String> {
+    let trade = trade_get_open(ctx, trade_id)?;
+    trade_cancel_internal(ctx, trade, DbTradeCancelReason::Cancelled);
+    Ok(())
+}
+
+/// Expire unanswered requests and cancel trades whose players went offline or apart
+#[spacetimedb::reducer]
+pub fn trade_tick(ctx: &ReducerContext, _schedule: TradeSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Reducer `trade_tick` may only be invoked by the scheduler".to_string());
+    }
+
+    let current_time = ctx.timestamp.to_micros_since_unix_epoch();
+
+    let expired: Vec<_> = ctx
+        .db
+        .trade()
+        .status()
+        .filter(&DbTradeStatus::Requested)
+        .filter(|trade| current_time - trade.requested_at_us > TRADE_REQUEST_TIMEOUT_US)
+        .collect();
+    for mut trade in expired {
+        trade.status = DbTradeStatus::Expired;
+        trade.ended_at_us = current_time;
+        ctx.db.trade().trade_id().update(trade);
+    }
+
+    let open: Vec<_> = ctx
+        .db
+        .trade()
+        .status()
+        .filter(&DbTradeStatus::Open)
+        .collect();
+    for trade in open {
+        let entities: Vec<_> = [trade.initiator, trade.partner]
+            .into_iter()
+            .map(|identity| trade_get_trader(ctx, identity))
+            .collect();
+        match (&entities[0], &entities[1]) {
+            (Ok(initiator), Ok(partner)) => {
+                if initiator.position.distance(&partner.position) > TRADE_RANGE {
+                    trade_cancel_internal(ctx, trade, DbTradeCancelReason::OutOfRange);
+                }
+            }
+            _ => trade_cancel_internal(ctx, trade, DbTradeCancelReason::Offline),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_items_are_combined_in_offer_order() {
+        let offer = trade_combine_offer(vec![
+            ItemRef::new(2, 5),
+            ItemRef::new(1, 3),
+            ItemRef::new(2, 7),
+        ])
+        .unwrap();
+
+        assert_eq!(offer, vec![ItemRef::new(2, 12), ItemRef::new(1, 3)]);
+    }
+
+    #[test]
+    fn empty_offer_is_allowed() {
+        assert_eq!(trade_combine_offer(Vec::new()), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn zero_quantity_is_rejected() {
+        let err = trade_combine_offer(vec![ItemRef::new(1, 3), ItemRef::new(2, 0)]).unwrap_err();
+        assert_eq!(err, "Offered quantities must be positive");
+    }
+
+    #[test]
+    fn combined_quantity_overflow_is_rejected() {
+        let err =
+            trade_combine_offer(vec![ItemRef::new(1, u32::MAX), ItemRef::new(1, 1)]).unwrap_err();
+        assert_eq!(err, "Offered quantity is too large");
+    }
+}
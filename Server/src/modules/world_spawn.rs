@@ -1,3 +1,4 @@
+use crate::modules::zone::zone_move_spawn;
 use crate::types::DbVector3;
 use spacetimedb::{ReducerContext, Table};
 
@@ -9,6 +10,8 @@ pub struct WorldSpawn {
     pub rotation: DbVector3,
 }
 
+// Spawn coordinates are copied verbatim from the editor
+#[allow(clippy::excessive_precision)]
 pub fn world_spawn_init(ctx: &ReducerContext) -> Result<(), String> {
     world_spawn_set(
         ctx,
@@ -29,7 +32,12 @@ pub fn world_spawn_set(
     position: DbVector3,
     rotation: DbVector3,
 ) -> Result<(), String> {
-    if let Some(mut spawn) = ctx.db.world_spawn().id().find(&id) {
+    // The safe zone around the main spawn moves with it
+    if id == 0 {
+        zone_move_spawn(ctx, &position);
+    }
+
+    if let Some(mut spawn) = ctx.db.world_spawn().id().find(id) {
         spawn.position = position;
         spawn.rotation = rotation;
         ctx.db.world_spawn().id().update(spawn);
@@ -0,0 +1,512 @@
+use crate::modules::admin::require_admin;
+use crate::modules::world_spawn::world_spawn;
+use crate::types::{DbBoundingBox, DbVector2, DbVector3};
+use spacetimedb::{ReducerContext, SpacetimeType, Table};
+
+/// Size of the grid cells zones are indexed by (in meters)
+const ZONE_CELL_SIZE: f32 = 32.0;
+/// Most grid cells a single zone may cover (64 x 64 cells, about 2km across)
+const MAX_ZONE_CELLS: i64 = 4096;
+/// Radius of the default safe zone around the world spawn
+const SPAWN_SAFE_ZONE_RADIUS: f32 = 30.0;
+/// Id of the default safe zone, which follows the world spawn
+const SPAWN_ZONE_ID: u32 = 1;
+
+/// How a zone overrides the PvP rules
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub enum DbZonePvpRule {
+    /// Use the global toggle and players' opt-in flags
+    Inherit,
+    /// Players can always damage each other (e.g., an arena)
+    Enabled,
+    /// Players can never damage each other
+    Disabled,
+}
+
+/// A sphere-shaped zone
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbZoneSphere {
+    pub center: DbVector3,
+    pub radius: f32,
+}
+
+/// A polygon on the XZ plane extruded between two heights
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbZonePolygonPrism {
+    /// Polygon corners in order (x = world X, y = world Z)
+    pub points: Vec<DbVector2>,
+    pub min_y: f32,
+    pub max_y: f32,
+}
+
+/// The volume a zone covers
+#[derive(SpacetimeType, Clone, Debug)]
+pub enum DbZoneShape {
+    /// Axis-aligned box
+    Box(DbBoundingBox),
+    Sphere(DbZoneSphere),
+    PolygonPrism(DbZonePolygonPrism),
+}
+
+/// Rules applied to everything inside a zone
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbZoneRules {
+    pub pvp: DbZonePvpRule,
+    /// Building pieces cannot be placed
+    pub no_build: bool,
+    /// Lootables cannot be looted
+    pub no_loot: bool,
+    /// Multiplier applied to movement speed
+    pub speed_multiplier: f32,
+}
+
+/// A region of the world with its own rules (e.g., a town or the area around spawn)
+#[spacetimedb::table(name = zone, public)]
+pub struct Zone {
+    #[primary_key]
+    #[auto_inc]
+    pub zone_id: u32,
+    pub name: String,
+    pub shape: DbZoneShape,
+    pub rules: DbZoneRules,
+}
+
+/// Grid cell overlapped by a zone, used to find zones near a point quickly
+#[spacetimedb::table(name = zone_cell)]
+pub struct ZoneCell {
+    #[primary_key]
+    #[auto_inc]
+    pub id: u64,
+    /// Packed grid coordinates (see `zone_cell_key`)
+    #[index(btree)]
+    pub cell_key: i64,
+    /// References Zone.zone_id
+    #[index(btree)]
+    pub zone_id: u32,
+}
+
+impl DbZoneShape {
+    /// Whether a point lies inside the volume
+    pub fn contains(&self, point: &DbVector3) -> bool {
+        match self {
+            DbZoneShape::Box(bounds) => bounds.contains(point),
+            DbZoneShape::Sphere(sphere) => sphere.center.distance(point) <= sphere.radius,
+            DbZoneShape::PolygonPrism(prism) => {
+                point.y >= prism.min_y
+                    && point.y <= prism.max_y
+                    && polygon_contains(&prism.points, point.x, point.z)
+            }
+        }
+    }
+
+    /// Horizontal extents of the volume as (min_x, min_z, max_x, max_z)
+    fn horizontal_bounds(&self) -> (f32, f32, f32, f32) {
+        match self {
+            DbZoneShape::Box(bounds) => (
+                bounds.center.x - bounds.half_extents.x,
+                bounds.center.z - bounds.half_extents.z,
+                bounds.center.x + bounds.half_extents.x,
+                bounds.center.z + bounds.half_extents.z,
+            ),
+            DbZoneShape::Sphere(sphere) => (
+                sphere.center.x - sphere.radius,
+                sphere.center.z - sphere.radius,
+                sphere.center.x + sphere.radius,
+                sphere.center.z + sphere.radius,
+            ),
+            DbZoneShape::PolygonPrism(prism) => prism.points.iter().fold(
+                (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                |(min_x, min_z, max_x, max_z), point| {
+                    (
+                        min_x.min(point.x),
+                        min_z.min(point.y),
+                        max_x.max(point.x),
+                        max_z.max(point.y),
+                    )
+                },
+            ),
+        }
+    }
+
+    /// Number of grid cells the horizontal bounds overlap
+    fn cell_count(&self) -> i64 {
+        let (min_x, min_z, max_x, max_z) = self.horizontal_bounds();
+        let (min_cell_x, min_cell_z) = zone_cell_coords(min_x, min_z);
+        let (max_cell_x, max_cell_z) = zone_cell_coords(max_x, max_z);
+        (max_cell_x as i64 - min_cell_x as i64 + 1) * (max_cell_z as i64 - min_cell_z as i64 + 1)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let finite = match self {
+            DbZoneShape::Box(bounds) => {
+                bounds.center.is_finite() && bounds.half_extents.is_finite()
+            }
+            DbZoneShape::Sphere(sphere) => sphere.center.is_finite() && sphere.radius.is_finite(),
+            DbZoneShape::PolygonPrism(prism) => {
+                prism.min_y.is_finite()
+                    && prism.max_y.is_finite()
+                    && prism
+                        .points
+                        .iter()
+                        .all(|point| point.x.is_finite() && point.y.is_finite())
+            }
+        };
+        if !finite {
+            return Err("Zone shape values must be finite numbers".to_string());
+        }
+
+        match self {
+            DbZoneShape::Box(bounds) => {
+                let extents = &bounds.half_extents;
+                if extents.x <= 0.0 || extents.y <= 0.0 || extents.z <= 0.0 {
+                    return Err("Zone box half extents must be positive".to_string());
+                }
+            }
+            DbZoneShape::Sphere(sphere) => {
+                if sphere.radius <= 0.0 {
+                    return Err("Zone sphere radius must be positive".to_string());
+                }
+            }
+            DbZoneShape::PolygonPrism(prism) => {
+                if prism.points.len() < 3 {
+                    return Err("Zone polygon needs at least 3 points".to_string());
+                }
+                if prism.min_y >= prism.max_y {
+                    return Err("Zone polygon min_y must be below max_y".to_string());
+                }
+            }
+        }
+
+        // Every overlapped cell is a row in the zone index
+        let cells = self.cell_count();
+        if cells > MAX_ZONE_CELLS {
+            return Err(format!(
+                "Zone is too large ({} grid cells, max {})",
+                cells, MAX_ZONE_CELLS
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for DbZoneRules {
+    fn default() -> Self {
+        Self {
+            pvp: DbZonePvpRule::Inherit,
+            no_build: false,
+            no_loot: false,
+            speed_multiplier: 1.0,
+        }
+    }
+}
+
+/// Even-odd test for a point inside a polygon on the XZ plane
+fn polygon_contains(points: &[DbVector2], x: f32, z: f32) -> bool {
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (a, b) = (&points[i], &points[j]);
+        // Points on the outline count as inside, like the box and sphere shapes
+        if polygon_edge_contains(a, b, x, z) {
+            return true;
+        }
+        if (a.y > z) != (b.y > z) && x < (b.x - a.x) * (z - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Whether a point lies on the edge from a to b (within a small tolerance)
+fn polygon_edge_contains(a: &DbVector2, b: &DbVector2, x: f32, z: f32) -> bool {
+    const TOLERANCE: f32 = 1e-4;
+    let cross = (b.x - a.x) * (z - a.y) - (b.y - a.y) * (x - a.x);
+    let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+    if cross.abs() > TOLERANCE * length.max(1.0) {
+        return false;
+    }
+    x >= a.x.min(b.x) - TOLERANCE
+        && x <= a.x.max(b.x) + TOLERANCE
+        && z >= a.y.min(b.y) - TOLERANCE
+        && z <= a.y.max(b.y) + TOLERANCE
+}
+
+fn zone_cell_coords(x: f32, z: f32) -> (i32, i32) {
+    (
+        (x / ZONE_CELL_SIZE).floor() as i32,
+        (z / ZONE_CELL_SIZE).floor() as i32,
+    )
+}
+
+fn zone_cell_key(cell_x: i32, cell_z: i32) -> i64 {
+    ((cell_x as i64) << 32) | (cell_z as u32 as i64)
+}
+
+/// Record every grid cell a zone's horizontal bounds overlap
+fn zone_index(ctx: &ReducerContext, zone: &Zone) {
+    let (min_x, min_z, max_x, max_z) = zone.shape.horizontal_bounds();
+    let (min_cell_x, min_cell_z) = zone_cell_coords(min_x, min_z);
+    let (max_cell_x, max_cell_z) = zone_cell_coords(max_x, max_z);
+
+    for cell_x in min_cell_x..=max_cell_x {
+        for cell_z in min_cell_z..=max_cell_z {
+            ctx.db.zone_cell().insert(ZoneCell {
+                id: 0,
+                cell_key: zone_cell_key(cell_x, cell_z),
+                zone_id: zone.zone_id,
+            });
+        }
+    }
+}
+
+fn zone_unindex(ctx: &ReducerContext, zone_id: u32) {
+    let ids: Vec<_> = ctx
+        .db
+        .zone_cell()
+        .zone_id()
+        .filter(zone_id)
+        .map(|cell| cell.id)
+        .collect();
+    for id in ids {
+        ctx.db.zone_cell().id().delete(id);
+    }
+}
+
+fn zone_insert(ctx: &ReducerContext, name: String, shape: DbZoneShape, rules: DbZoneRules) -> Zone {
+    let zone = ctx.db.zone().insert(Zone {
+        zone_id: 0,
+        name,
+        shape,
+        rules,
+    });
+    zone_index(ctx, &zone);
+    zone
+}
+
+/// Create the default safe zone around the world spawn
+pub fn zone_init(ctx: &ReducerContext) -> Result<(), String> {
+    let center = ctx
+        .db
+        .world_spawn()
+        .id()
+        .find(0)
+        .map_or(DbVector3::default(), |spawn| spawn.position);
+
+    // Spawn - zone_id 1 (SPAWN_ZONE_ID)
+    zone_insert(
+        ctx,
+        "Spawn".to_string(),
+        DbZoneShape::Sphere(DbZoneSphere {
+            center,
+            radius: SPAWN_SAFE_ZONE_RADIUS,
+        }),
+        DbZoneRules {
+            pvp: DbZonePvpRule::Disabled,
+            no_build: true,
+            ..Default::default()
+        },
+    );
+
+    log::info!("Initialized default zones");
+    Ok(())
+}
+
+/// Move the spawn safe zone to a new world spawn position
+pub fn zone_move_spawn(ctx: &ReducerContext, center: &DbVector3) {
+    let mut zone = match ctx.db.zone().zone_id().find(SPAWN_ZONE_ID) {
+        Some(zone) => zone,
+        None => return,
+    };
+    match &mut zone.shape {
+        DbZoneShape::Sphere(sphere) => sphere.center = center.clone(),
+        _ => return,
+    }
+
+    zone_unindex(ctx, SPAWN_ZONE_ID);
+    zone_index(ctx, &zone);
+    ctx.db.zone().zone_id().update(zone);
+    log::info!("Moved the spawn safe zone to the world spawn");
+}
+
+/// Find all zones containing a point
+/// Only zones indexed in the point's grid cell are tested
+pub fn zone_find_at(ctx: &ReducerContext, position: &DbVector3) -> Vec<Zone> {
+    let (cell_x, cell_z) = zone_cell_coords(position.x, position.z);
+    ctx.db
+        .zone_cell()
+        .cell_key()
+        .filter(zone_cell_key(cell_x, cell_z))
+        .filter_map(|cell| ctx.db.zone().zone_id().find(cell.zone_id))
+        .filter(|zone| zone.shape.contains(position))
+        .collect()
+}
+
+/// The PvP rule at a point; Disabled wins over Enabled when zones overlap
+pub fn zone_pvp_rule_at(ctx: &ReducerContext, position: &DbVector3) -> DbZonePvpRule {
+    zone_find_at(ctx, position)
+        .iter()
+        .fold(DbZonePvpRule::Inherit, |rule, zone| {
+            match (rule, zone.rules.pvp) {
+                (DbZonePvpRule::Disabled, _) | (_, DbZonePvpRule::Disabled) => {
+                    DbZonePvpRule::Disabled
+                }
+                (DbZonePvpRule::Enabled, _) | (_, DbZonePvpRule::Enabled) => DbZonePvpRule::Enabled,
+                _ => DbZonePvpRule::Inherit,
+            }
+        })
+}
+
+/// Fail if building is not allowed at a point
+pub fn zone_check_build(ctx: &ReducerContext, position: &DbVector3) -> Result<(), String> {
+    match zone_find_at(ctx, position)
+        .into_iter()
+        .find(|zone| zone.rules.no_build)
+    {
+        Some(zone) => Err(format!("Building is not allowed in {}", zone.name)),
+        None => Ok(()),
+    }
+}
+
+/// Fail if looting is not allowed at a point
+pub fn zone_check_loot(ctx: &ReducerContext, position: &DbVector3) -> Result<(), String> {
+    match zone_find_at(ctx, position)
+        .into_iter()
+        .find(|zone| zone.rules.no_loot)
+    {
+        Some(zone) => Err(format!("Looting is not allowed in {}", zone.name)),
+        None => Ok(()),
+    }
+}
+
+/// Combined movement speed multiplier of all zones at a point
+pub fn zone_speed_multiplier_at(ctx: &ReducerContext, position: &DbVector3) -> f32 {
+    zone_find_at(ctx, position)
+        .iter()
+        .map(|zone| zone.rules.speed_multiplier)
+        .product()
+}
+
+/// Creates a new zone (admin only)
+#[spacetimedb::reducer]
+pub fn zone_create(
+    ctx: &ReducerContext,
+    name: String,
+    shape: DbZoneShape,
+    rules: DbZoneRules,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    shape.validate()?;
+    if rules.speed_multiplier < 0.0 {
+        return Err("Zone speed multiplier cannot be negative".to_string());
+    }
+
+    let zone = zone_insert(ctx, name, shape, rules);
+    log::info!("Created zone {} ({})", zone.zone_id, zone.name);
+    Ok(())
+}
+
+/// Replace a zone's rules (admin only)
+#[spacetimedb::reducer]
+pub fn zone_set_rules(
+    ctx: &ReducerContext,
+    zone_id: u32,
+    rules: DbZoneRules,
+) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if rules.speed_multiplier < 0.0 {
+        return Err("Zone speed multiplier cannot be negative".to_string());
+    }
+
+    let mut zone = ctx
+        .db
+        .zone()
+        .zone_id()
+        .find(zone_id)
+        .ok_or(format!("Zone {} does not exist", zone_id))?;
+    zone.rules = rules;
+    ctx.db.zone().zone_id().update(zone);
+
+    log::info!("Updated rules for zone {}", zone_id);
+    Ok(())
+}
+
+/// Deletes a zone (admin only)
+#[spacetimedb::reducer]
+pub fn zone_delete(ctx: &ReducerContext, zone_id: u32) -> Result<(), String> {
+    require_admin(ctx)?;
+
+    if !ctx.db.zone().zone_id().delete(zone_id) {
+        return Err(format!("Zone {} does not exist", zone_id));
+    }
+    zone_unindex(ctx, zone_id);
+
+    log::info!("Deleted zone {}", zone_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polygon(points: &[(f32, f32)]) -> Vec<DbVector2> {
+        points.iter().map(|&(x, y)| DbVector2 { x, y }).collect()
+    }
+
+    fn square() -> Vec<DbVector2> {
+        polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)])
+    }
+
+    #[test]
+    fn point_inside_and_outside_polygon() {
+        let points = square();
+        assert!(polygon_contains(&points, 2.0, 2.0));
+        assert!(!polygon_contains(&points, 5.0, 2.0));
+        assert!(!polygon_contains(&points, 2.0, -1.0));
+    }
+
+    #[test]
+    fn points_on_every_edge_are_inside() {
+        let points = square();
+        for (x, z) in [(2.0, 0.0), (4.0, 2.0), (2.0, 4.0), (0.0, 2.0)] {
+            assert!(polygon_contains(&points, x, z), "({}, {})", x, z);
+        }
+    }
+
+    #[test]
+    fn vertices_are_inside() {
+        let points = square();
+        for point in &points {
+            assert!(polygon_contains(&points, point.x, point.y), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn points_in_line_with_an_edge_but_beyond_it_are_outside() {
+        let points = square();
+        assert!(!polygon_contains(&points, 6.0, 0.0));
+        assert!(!polygon_contains(&points, 0.0, -2.0));
+    }
+
+    #[test]
+    fn diagonal_edges_of_a_concave_polygon() {
+        // Arrow-head shape with a notch cut into its right side
+        let points = polygon(&[(0.0, 0.0), (4.0, 0.0), (2.0, 2.0), (4.0, 4.0), (0.0, 4.0)]);
+        assert!(polygon_contains(&points, 3.0, 1.0));
+        assert!(polygon_contains(&points, 2.0, 2.0));
+        assert!(polygon_contains(&points, 1.0, 2.0));
+        assert!(!polygon_contains(&points, 3.0, 2.0));
+    }
+
+    #[test]
+    fn zone_cell_count_covers_partial_cells() {
+        let shape = DbZoneShape::Sphere(DbZoneSphere {
+            center: DbVector3::default(),
+            radius: 1.0,
+        });
+        // Straddles the origin, so it touches a 2x2 block of cells
+        assert_eq!(shape.cell_count(), 4);
+    }
+}
@@ -23,6 +23,156 @@ impl DbVector3 {
     pub fn distance(&self, other: &DbVector3) -> f32 {
         ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)).sqrt()
     }
+
+    /// Component-wise sum of two vectors
+    pub fn add(&self, other: &DbVector3) -> DbVector3 {
+        DbVector3 {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+
+    /// Component-wise difference of two vectors
+    pub fn sub(&self, other: &DbVector3) -> DbVector3 {
+        DbVector3 {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+
+    /// Multiply every component by a scalar
+    pub fn scale(&self, factor: f32) -> DbVector3 {
+        DbVector3 {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    /// Dot product of two vectors
+    pub fn dot(&self, other: &DbVector3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Length of the vector
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    /// Whether every component is a finite number (not NaN or infinite)
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+
+    /// Linear interpolation towards another point (t = 0 is self, t = 1 is other)
+    pub fn lerp(&self, other: &DbVector3, t: f32) -> DbVector3 {
+        self.add(&other.sub(self).scale(t))
+    }
+
+    /// Undo a rotation by Unity euler angles in degrees (which apply Z, then X, then Y)
+    pub fn inverse_rotate_euler(&self, euler: &DbVector3) -> DbVector3 {
+        self.rotate_y(-euler.y).rotate_x(-euler.x).rotate_z(-euler.z)
+    }
+
+    fn rotate_x(&self, degrees: f32) -> DbVector3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        DbVector3 {
+            x: self.x,
+            y: self.y * cos - self.z * sin,
+            z: self.y * sin + self.z * cos,
+        }
+    }
+
+    fn rotate_y(&self, degrees: f32) -> DbVector3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        DbVector3 {
+            x: self.x * cos + self.z * sin,
+            y: self.y,
+            z: -self.x * sin + self.z * cos,
+        }
+    }
+
+    fn rotate_z(&self, degrees: f32) -> DbVector3 {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        DbVector3 {
+            x: self.x * cos - self.y * sin,
+            y: self.x * sin + self.y * cos,
+            z: self.z,
+        }
+    }
+}
+
+/// An axis-aligned box in an object's local space
+#[derive(SpacetimeType, Clone, Debug)]
+pub struct DbBoundingBox {
+    /// Center of the box relative to the object's pivot
+    pub center: DbVector3,
+    /// Half the size of the box along each local axis
+    pub half_extents: DbVector3,
+}
+
+impl DbBoundingBox {
+    /// Find where the segment from start to end enters this box once it has been placed at
+    /// the given position and rotation (slab test in the box's local space)
+    /// Returns the fraction along the segment (0 = start, 1 = end), or None if it misses
+    pub fn segment_intersection(
+        &self,
+        position: &DbVector3,
+        rotation: &DbVector3,
+        start: &DbVector3,
+        end: &DbVector3,
+    ) -> Option<f32> {
+        let local_start = start
+            .sub(position)
+            .inverse_rotate_euler(rotation)
+            .sub(&self.center);
+        let local_end = end
+            .sub(position)
+            .inverse_rotate_euler(rotation)
+            .sub(&self.center);
+        let direction = local_end.sub(&local_start);
+
+        let axes = [
+            (local_start.x, direction.x, self.half_extents.x),
+            (local_start.y, direction.y, self.half_extents.y),
+            (local_start.z, direction.z, self.half_extents.z),
+        ];
+
+        let mut t_min: f32 = 0.0;
+        let mut t_max: f32 = 1.0;
+        for (origin, delta, half_extent) in axes {
+            if delta.abs() < f32::EPSILON {
+                // Segment is parallel to this slab - it must start inside it
+                if origin.abs() > half_extent {
+                    return None;
+                }
+            } else {
+                let t1 = (-half_extent - origin) / delta;
+                let t2 = (half_extent - origin) / delta;
+                t_min = t_min.max(t1.min(t2));
+                t_max = t_max.min(t1.max(t2));
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+
+        Some(t_min)
+    }
+
+    /// Radius of a sphere around the pivot that fully contains the box
+    pub fn bounding_radius(&self) -> f32 {
+        self.center.length() + self.half_extents.length()
+    }
+
+    /// Whether a point lies inside the box (without rotation)
+    pub fn contains(&self, point: &DbVector3) -> bool {
+        (point.x - self.center.x).abs() <= self.half_extents.x
+            && (point.y - self.center.y).abs() <= self.half_extents.y
+            && (point.z - self.center.z).abs() <= self.half_extents.z
+    }
 }
 
 #[derive(SpacetimeType, Clone, Debug)]
@@ -36,3 +186,146 @@ impl Default for DbVector2 {
         Self { x: 0.0, y: 0.0 }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec3(x: f32, y: f32, z: f32) -> DbVector3 {
+        DbVector3 { x, y, z }
+    }
+
+    fn assert_close(actual: &DbVector3, expected: &DbVector3) {
+        assert!(
+            actual.distance(expected) < 1e-4,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    fn unit_box() -> DbBoundingBox {
+        DbBoundingBox {
+            center: DbVector3::default(),
+            half_extents: vec3(1.0, 1.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn inverse_rotation_undoes_a_yaw() {
+        // A 90 degree yaw turns +X into -Z in Unity
+        let local = vec3(0.0, 0.0, -1.0).inverse_rotate_euler(&vec3(0.0, 90.0, 0.0));
+        assert_close(&local, &vec3(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn inverse_rotation_undoes_a_combined_rotation() {
+        let euler = vec3(30.0, 45.0, 60.0);
+        let point = vec3(1.0, 2.0, 3.0);
+        // Unity applies Z, then X, then Y
+        let rotated = point.rotate_z(euler.z).rotate_x(euler.x).rotate_y(euler.y);
+        assert_close(&rotated.inverse_rotate_euler(&euler), &point);
+    }
+
+    #[test]
+    fn segment_through_box_enters_at_near_face() {
+        let t = unit_box().segment_intersection(
+            &DbVector3::default(),
+            &DbVector3::default(),
+            &vec3(-5.0, 0.0, 0.0),
+            &vec3(5.0, 0.0, 0.0),
+        );
+        assert!((t.unwrap() - 0.4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rotated_box_blocks_segment_its_unrotated_bounds_miss() {
+        // A thin wall along X, rotated a quarter turn so it runs along Z
+        let wall = DbBoundingBox {
+            center: DbVector3::default(),
+            half_extents: vec3(2.0, 1.0, 0.25),
+        };
+        let position = vec3(10.0, 0.0, 0.0);
+        let start = vec3(5.0, 0.0, 1.5);
+        let end = vec3(15.0, 0.0, 1.5);
+
+        assert!(wall
+            .segment_intersection(&position, &DbVector3::default(), &start, &end)
+            .is_none());
+
+        let t = wall
+            .segment_intersection(&position, &vec3(0.0, 90.0, 0.0), &start, &end)
+            .unwrap();
+        assert!((t - 0.475).abs() < 1e-4);
+    }
+
+    #[test]
+    fn segment_grazing_a_face_counts_as_a_hit() {
+        let bounds = unit_box();
+        let rotation = DbVector3::default();
+        let position = DbVector3::default();
+
+        // Runs along the top face
+        let t = bounds.segment_intersection(
+            &position,
+            &rotation,
+            &vec3(-5.0, 1.0, 0.0),
+            &vec3(5.0, 1.0, 0.0),
+        );
+        assert!(t.is_some());
+
+        // Just above the top face
+        let t = bounds.segment_intersection(
+            &position,
+            &rotation,
+            &vec3(-5.0, 1.01, 0.0),
+            &vec3(5.0, 1.01, 0.0),
+        );
+        assert!(t.is_none());
+    }
+
+    #[test]
+    fn segment_ending_at_or_short_of_a_face() {
+        let bounds = unit_box();
+        let rotation = DbVector3::default();
+        let position = DbVector3::default();
+
+        let t = bounds.segment_intersection(
+            &position,
+            &rotation,
+            &vec3(-5.0, 0.0, 0.0),
+            &vec3(-1.0, 0.0, 0.0),
+        );
+        assert!((t.unwrap() - 1.0).abs() < 1e-5);
+
+        let t = bounds.segment_intersection(
+            &position,
+            &rotation,
+            &vec3(-5.0, 0.0, 0.0),
+            &vec3(-1.1, 0.0, 0.0),
+        );
+        assert!(t.is_none());
+    }
+
+    #[test]
+    fn segment_starting_inside_box_hits_immediately() {
+        let t = unit_box().segment_intersection(
+            &DbVector3::default(),
+            &vec3(0.0, 45.0, 0.0),
+            &vec3(0.5, 0.0, 0.0),
+            &vec3(5.0, 0.0, 0.0),
+        );
+        assert_eq!(t, Some(0.0));
+    }
+
+    #[test]
+    fn segment_beside_box_misses() {
+        let t = unit_box().segment_intersection(
+            &DbVector3::default(),
+            &DbVector3::default(),
+            &vec3(-5.0, 0.0, 3.0),
+            &vec3(5.0, 0.0, 3.0),
+        );
+        assert!(t.is_none());
+    }
+}